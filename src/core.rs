@@ -20,9 +20,15 @@ use std::{
 use nix::{
     fcntl,
     sys::{
+        resource,
+        resource::UsageWho,
         signal::Signal,
+        termios,
+        termios::Termios,
         time::{TimeSpec, TimeVal},
     },
+    time,
+    time::ClockId,
     unistd,
     unistd::Pid,
 };
@@ -32,17 +38,32 @@ use self::{
     database::DataBase,
     options::Options,
 };
-use crate::{core::jobtable::JobEntry, elements::substitution::Substitution, error, proc_ctrl, signal};
+use crate::{
+    core::jobtable::JobEntry,
+    elements::{io::FdTable, substitution::Substitution},
+    error, proc_ctrl, signal,
+    utils::clock,
+};
 
+#[derive(Debug, Clone, Copy)]
 pub struct MeasuredTime {
-    pub real: TimeSpec,
-    pub user: TimeVal,
-    pub sys:  TimeVal,
+    pub real:  TimeSpec,
+    pub user:  TimeVal,
+    pub sys:   TimeVal,
+    pub posix: bool,
 }
 
-impl Default for MeasuredTime {
-    fn default() -> Self {
-        Self { real: TimeSpec::new(0, 0), user: TimeVal::new(0, 0), sys: TimeVal::new(0, 0) }
+impl MeasuredTime {
+    pub fn start(posix: bool) -> Self {
+        let self_usage = resource::getrusage(UsageWho::RUSAGE_SELF).unwrap();
+        let children_usage = resource::getrusage(UsageWho::RUSAGE_CHILDREN).unwrap();
+
+        Self {
+            real: time::clock_gettime(ClockId::CLOCK_MONOTONIC).unwrap(),
+            user: self_usage.user_time() + children_usage.user_time(),
+            sys: self_usage.system_time() + children_usage.system_time(),
+            posix,
+        }
     }
 }
 
@@ -56,29 +77,41 @@ pub struct ShellCore {
     pub builtins: HashMap<String, fn(&mut ShellCore, &mut Vec<String>) -> i32>,
     pub substitution_builtins: HashMap<String, fn(&mut ShellCore, &mut Vec<String>, &mut Vec<Substitution>) -> i32>,
     pub sigint: Arc<AtomicBool>,
+    pub sigchld: Arc<AtomicBool>,
+    pub sighup: Arc<AtomicBool>,
     pub trapped: Vec<(Arc<AtomicBool>, String)>,
     pub traplist: Vec<(i32, String)>,
     pub is_subshell: bool,
     pub source_function_level: i32,
     pub source_files: Vec<String>,
+    pub funcname_stack: Vec<String>,
+    pub function_level: i32,
     pub eval_level: i32,
+    pub arith_level: i32,
     pub loop_level: i32,
     pub break_counter: i32,
     pub continue_counter: i32,
     pub return_flag: bool,
     pub compat_bash: bool,
     pub tty_fd: Option<OwnedFd>,
+    pub tty_termios: Option<Termios>,
+    pub fds: FdTable,
     pub job_table: Vec<JobEntry>,
     pub job_table_priority: Vec<usize>,
+    pub saved_statuses: Vec<(Pid, i32)>,
+    pub fg_depth: usize, // nesting of foreground jobs run in this process
+    pub suspended_job: Option<JobEntry>, // a compound command stopped by ^Z, left until the top-level job
+    pub runs_suspended_rest: bool, // this process continues the rest of a suspended compound command
     current_dir: Option<path::PathBuf>, // the_current_working_directory
     pub completion: Completion,
-    pub measured_time: MeasuredTime,
+    pub start_time: i64,
     pub options: Options,
     pub shopts: Options,
     pub suspend_e_option: bool,
     pub script_name: String,
     pub exit_script: String,
     pub exit_script_run: bool,
+    pub exit_warned: bool,
     pub valid_assoc_expand_once: bool,
 }
 
@@ -98,6 +131,8 @@ impl ShellCore {
             let _ = self.db.set_param("PS2", "> ", None);
             let fd = fcntl::fcntl(0, fcntl::F_DUPFD_CLOEXEC(255)).expect("sush(fatal): Can't allocate fd for tty FD");
             self.tty_fd = Some(unsafe { OwnedFd::from_raw_fd(fd) });
+            self.tty_termios = termios::tcgetattr(self.tty_fd.as_ref().unwrap()).ok();
+            self.fds.set_internal(fd, "tty");
         } else {
             self.db.flags += "h";
         }
@@ -120,6 +155,7 @@ impl ShellCore {
             let zero = "0".to_string();
             let _ = self.db.set_param2("BASH_LINENO", &zero, &zero, None);
             let _ = self.db.set_param2("BASH_SOURCE", &zero, &self.script_name, None);
+            self.funcname_stack.push("main".to_string());
         }
     }
 
@@ -127,9 +163,12 @@ impl ShellCore {
         ShellCore {
             db: DataBase::new(),
             sigint: Arc::new(AtomicBool::new(false)),
+            sigchld: Arc::new(AtomicBool::new(false)),
+            sighup: Arc::new(AtomicBool::new(false)),
             options: Options::new_as_basic_opts(),
             shopts: Options::new_as_shopts(),
             script_name: "-".to_string(),
+            start_time: clock::get_epochseconds().parse().unwrap_or(0),
             ..Default::default()
         }
     }
@@ -138,6 +177,8 @@ impl ShellCore {
         if unistd::isatty(0) == Ok(true) {
             let fd = fcntl::fcntl(0, fcntl::F_DUPFD_CLOEXEC(255)).expect("sush(fatal): Can't allocate fd for tty FD");
             self.tty_fd = Some(unsafe { OwnedFd::from_raw_fd(fd) });
+            self.tty_termios = termios::tcgetattr(self.tty_fd.as_ref().unwrap()).ok();
+            self.fds.set_internal(fd, "tty");
         }
 
         self.init_current_directory();
@@ -231,6 +272,49 @@ impl ShellCore {
         multi_ps4
     }
 
+    pub fn push_call_stack(&mut self, funcname: &str, source: &str, is_function: bool) {
+        let lineno = self.db.get_param("LINENO").unwrap_or("0".to_string());
+        let mut linenos = self.db.get_vec("BASH_LINENO", false).unwrap_or_default();
+        let mut sources = self.db.get_vec("BASH_SOURCE", false).unwrap_or_default();
+        linenos.insert(0, lineno);
+        sources.insert(0, source.to_string());
+        self.funcname_stack.insert(0, funcname.to_string());
+        if is_function {
+            self.function_level += 1;
+        }
+        self.set_call_stack_params(linenos, sources);
+    }
+
+    pub fn pop_call_stack(&mut self, is_function: bool) {
+        let mut linenos = self.db.get_vec("BASH_LINENO", false).unwrap_or_default();
+        let mut sources = self.db.get_vec("BASH_SOURCE", false).unwrap_or_default();
+        if !linenos.is_empty() {
+            linenos.remove(0);
+        }
+        if !sources.is_empty() {
+            sources.remove(0);
+        }
+        if !self.funcname_stack.is_empty() {
+            self.funcname_stack.remove(0);
+        }
+        if is_function {
+            self.function_level -= 1;
+        }
+        self.set_call_stack_params(linenos, sources);
+    }
+
+    fn set_call_stack_params(&mut self, linenos: Vec<String>, sources: Vec<String>) {
+        let _ = self.db.set_array("BASH_LINENO", Some(linenos), None);
+        let _ = self.db.set_array("BASH_SOURCE", Some(sources), None);
+
+        // FUNCNAME is visible only while a shell function is being executed, as in Bash
+        let funcnames = match self.function_level > 0 {
+            true => Some(self.funcname_stack.clone()),
+            false => None,
+        };
+        let _ = self.db.set_array("FUNCNAME", funcnames, None);
+    }
+
     pub fn replace_alias(&mut self, word: &mut String) -> bool {
         let before = word.clone();
         match self.replace_alias_core(word) {
@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: BSD-3-Clause
 
 mod alias;
+mod caller;
 mod cd;
 mod command;
 pub mod compgen;
@@ -16,6 +17,7 @@ mod history;
 mod job_commands;
 mod loop_control;
 pub mod option;
+mod parallel;
 pub mod parameter;
 mod printf;
 mod pwd;
@@ -25,7 +27,19 @@ mod trap;
 mod type_;
 mod unset;
 
-use crate::{Feeder, Script, ShellCore, elements::expr::arithmetic::ArithmeticExpr, error::parse::ParseError, exit};
+use nix::sys::{
+    resource,
+    resource::UsageWho,
+    time::TimeVal,
+};
+
+use crate::{
+    Feeder, Script, ShellCore,
+    elements::{command::simple::SimpleCommand, expr::arithmetic::ArithmeticExpr, io::pipe::Pipe},
+    error::parse::ParseError,
+    exit, proc_ctrl,
+    utils::arg,
+};
 
 pub fn error_exit(exit_status: i32, name: &str, msg: &str, core: &mut ShellCore) -> i32 {
     let shellname = core.db.get_param("0").unwrap();
@@ -38,6 +52,16 @@ pub fn error_exit(exit_status: i32, name: &str, msg: &str, core: &mut ShellCore)
     exit_status
 }
 
+/* runs words as a simple command and waits for it */
+pub fn run_simple_command(core: &mut ShellCore, words: Vec<String>, pipe: &mut Pipe) -> i32 {
+    let mut command = SimpleCommand::default();
+    command.args = words;
+    if let Ok(pid) = command.exec_command(core, pipe) {
+        proc_ctrl::wait_pipeline(core, vec![pid], false, None);
+    }
+    core.db.exit_status
+}
+
 impl ShellCore {
     pub fn set_builtins(&mut self) {
         self.builtins.insert(":".to_string(), true_);
@@ -46,6 +70,7 @@ impl ShellCore {
         self.builtins.insert("bind".to_string(), bind);
         self.builtins.insert("break".to_string(), loop_control::break_);
         self.builtins.insert("builtin".to_string(), command::builtin);
+        self.builtins.insert("caller".to_string(), caller::caller);
         self.builtins.insert("cd".to_string(), cd::cd);
         self.builtins.insert("command".to_string(), command::command);
         self.builtins.insert("compgen".to_string(), compgen::compgen);
@@ -66,6 +91,8 @@ impl ShellCore {
         self.builtins.insert("jobs".to_string(), job_commands::jobs);
         self.builtins.insert("kill".to_string(), job_commands::kill);
         self.builtins.insert("let".to_string(), let_);
+        self.builtins.insert("logout".to_string(), logout);
+        self.builtins.insert("sush_parallel".to_string(), parallel::parallel);
         self.builtins.insert("printf".to_string(), printf::printf);
         self.builtins.insert("pwd".to_string(), pwd::pwd);
         self.builtins.insert("read".to_string(), read::read);
@@ -75,6 +102,8 @@ impl ShellCore {
         self.builtins.insert("type".to_string(), type_::type_);
         self.builtins.insert("shift".to_string(), option::shift);
         self.builtins.insert("shopt".to_string(), option::shopt);
+        self.builtins.insert("suspend".to_string(), job_commands::suspend);
+        self.builtins.insert("times".to_string(), times);
         self.builtins.insert("unalias".to_string(), alias::unalias);
         self.builtins.insert("unset".to_string(), unset::unset);
         self.builtins.insert("source".to_string(), source::source);
@@ -126,7 +155,24 @@ pub fn eval(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
 
 pub fn exit(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
     if core.db.flags.contains('i') {
-        eprintln!("exit");
+        match core.shopts.query("login_shell") {
+            true => eprintln!("logout"),
+            false => eprintln!("exit"),
+        }
+    }
+    exit_or_logout(core, args)
+}
+
+pub fn logout(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
+    if !core.shopts.query("login_shell") {
+        return error_exit(1, &args[0], "not login shell: use `exit'", core);
+    }
+    exit_or_logout(core, args)
+}
+
+fn exit_or_logout(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
+    if exit::jobs_remain(core) {
+        return 1;
     }
     if args.len() > 1 {
         match &args[1].parse::<i32>() {
@@ -137,6 +183,28 @@ pub fn exit(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
     exit::normal(core)
 }
 
+fn format_time(t: TimeVal) -> String {
+    format!("{}m{}.{:03}s", t.tv_sec() / 60, t.tv_sec() % 60, t.tv_usec() / 1000)
+}
+
+pub fn times(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
+    let args = arg::dissolve_options(args);
+    if args.len() > 1 && args[1] != "--" && args[1].starts_with("-") {
+        let msg = format!("{}: invalid option", &args[1]);
+        error_exit(2, &args[0], &msg, core);
+        eprintln!("times: usage: times");
+        return 2;
+    }
+
+    for who in [UsageWho::RUSAGE_SELF, UsageWho::RUSAGE_CHILDREN] {
+        match resource::getrusage(who) {
+            Ok(u) => println!("{} {}", format_time(u.user_time()), format_time(u.system_time())),
+            Err(e) => return error_exit(1, &args[0], e.desc(), core),
+        }
+    }
+    0
+}
+
 pub fn false_(_: &mut ShellCore, _: &mut Vec<String>) -> i32 {
     1
 }
@@ -150,6 +218,11 @@ pub fn bind(_: &mut ShellCore, _: &mut Vec<String>) -> i32 {
 }
 
 pub fn debug(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
+    if args.len() > 1 && args[1] == "fds" {
+        core.fds.list().iter().for_each(|s| println!("{}", s));
+        return 0;
+    }
+
     dbg!("{:?}", &args);
     dbg!("{:?}", &core.db.get_param("depth"));
     0
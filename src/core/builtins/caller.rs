@@ -0,0 +1,45 @@
+// SPDX-FileCopyrightText: 2025 Ryuichi Ueda <ryuichiueda@gmail.com>
+// SPDX-License-Identifier: BSD-3-Clause
+
+use crate::ShellCore;
+
+pub fn caller(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
+    if args.len() > 1 && args[1] == "--" {
+        args.remove(1);
+    }
+
+    if args.len() > 2 {
+        eprintln!("caller: usage: caller [expr]");
+        return 2;
+    }
+
+    let linenos = core.db.get_vec("BASH_LINENO", false).unwrap_or_default();
+    let sources = core.db.get_vec("BASH_SOURCE", false).unwrap_or_default();
+
+    if args.len() == 1 {
+        if linenos.is_empty() {
+            return 1;
+        }
+        let source = sources.get(1).map(|s| s.as_str()).unwrap_or("NULL");
+        println!("{} {}", &linenos[0], source);
+        return 0;
+    }
+
+    let n = match args[1].parse::<usize>() {
+        Ok(n) => n,
+        Err(_) => {
+            let msg = format!("{}: invalid number", &args[1]);
+            super::error_exit(2, &args[0], &msg, core);
+            eprintln!("caller: usage: caller [expr]");
+            return 2;
+        },
+    };
+
+    match (linenos.get(n), core.funcname_stack.get(n + 1), sources.get(n + 1)) {
+        (Some(lineno), Some(funcname), Some(source)) => {
+            println!("{} {} {}", lineno, funcname, source);
+            0
+        },
+        _ => 1,
+    }
+}
@@ -3,8 +3,8 @@
 
 use crate::{
     ShellCore,
-    elements::{command::simple::SimpleCommand, io::pipe::Pipe},
-    error, proc_ctrl,
+    elements::io::pipe::Pipe,
+    error,
     utils::{arg, file},
 };
 
@@ -91,12 +91,5 @@ pub fn command(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
         return core.builtins[&words[0]](core, &mut words);
     }
 
-    let mut command = SimpleCommand::default();
-    let mut pipe = Pipe::new("".to_string());
-    command.args = words;
-    if let Ok(pid) = command.exec_command(core, &mut pipe) {
-        proc_ctrl::wait_pipeline(core, vec![pid], false, false);
-    }
-
-    core.db.exit_status
+    super::run_simple_command(core, words, &mut Pipe::new("".to_string()))
 }
@@ -1,76 +1,67 @@
 // SPDX-FileCopyrightText: 2025 Ryuichi Ueda <ryuichiueda@gmail.com>
 // SPDX-License-Identifier: BSD-3-Clause
 
-use std::{
-    ffi::CString,
-    io,
-    io::{Write, stdout},
-};
+use std::io::{Write, stdout};
 
-use crate::{Feeder, ShellCore, elements::ansi_c_str::AnsiCString, error::exec::ExecError, utils::c_string};
+use nix::errno::Errno;
 
-fn arg_to_c_str(arg: &String, core: &mut ShellCore) -> Result<CString, ExecError> {
-    let mut f = Feeder::new(&arg);
-    let ans = match AnsiCString::parse(&mut f, core, true) {
-        Ok(Some(mut ansi_c_str)) => c_string::to_carg(&ansi_c_str.eval()),
-        Ok(None) => c_string::to_carg(arg),
-        Err(e) => return Err(ExecError::ParseError(e)),
-    };
+use super::error_exit;
+use crate::{
+    ShellCore,
+    utils::{c_string, c_string::EscapeMode},
+};
 
-    Ok(ans)
+fn is_option(arg: &str) -> bool {
+    arg.len() > 1 && arg.starts_with('-') && arg[1..].chars().all(|c| "neE".contains(c))
 }
 
 pub fn echo(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
-    let mut first = true;
-    let mut e_opt = false;
-    let mut n_opt = false;
-
-    if args.len() == 1 {
-        println!("");
-        return 0;
-    }
+    let xpg_echo = core.shopts.query("xpg_echo");
+    let mut escape = xpg_echo;
+    let mut newline = true;
+    let mut start = 1;
 
-    match args[1].as_ref() {
-        "-ne" | "-en" => {
-            e_opt = true;
-            n_opt = true;
-            args.remove(1);
-        },
-        "-e" => {
-            e_opt = true;
-            args.remove(1);
-        },
-        "-n" => {
-            n_opt = true;
-            args.remove(1);
-        },
-        _ => {},
+    if !(xpg_echo && core.options.query("posix")) {
+        while start < args.len() && is_option(&args[start]) {
+            for c in args[start][1..].chars() {
+                match c {
+                    'n' => newline = false,
+                    'e' => escape = true,
+                    _ => escape = false,
+                }
+            }
+            start += 1;
+        }
     }
 
-    for a in &args[1..] {
-        if !first {
-            let _ = io::stdout().write(b" ");
+    let mut out = vec![];
+    for (i, a) in args[start..].iter().enumerate() {
+        if i > 0 {
+            out.push(b' ');
         }
-        first = false;
 
-        let bytes = match e_opt {
-            false => c_string::to_carg(a).into_bytes(),
-            true => match arg_to_c_str(a, core) {
-                Ok(v) => v.into_bytes(),
-                Err(e) => {
-                    e.print(core);
-                    return 1;
-                },
-            },
-        };
+        let bytes = c_string::to_carg(a).into_bytes();
+        if !escape {
+            out.extend(bytes);
+            continue;
+        }
 
-        let _ = io::stdout().write_all(&bytes).unwrap();
+        let (bytes, stop) = c_string::unescape(&bytes, EscapeMode::Echo);
+        out.extend(bytes);
+        if stop {
+            newline = false;
+            break;
+        }
     }
 
-    if !n_opt {
-        let _ = io::stdout().write(b"\n");
+    if newline {
+        out.push(b'\n');
     }
 
-    stdout().flush().unwrap();
+    let mut stdout = stdout();
+    if let Err(e) = stdout.write_all(&out).and_then(|_| stdout.flush()) {
+        let errno = Errno::from_raw(e.raw_os_error().unwrap_or(0));
+        return error_exit(1, "echo", &format!("write error: {}", errno.desc()), core);
+    }
     0
 }
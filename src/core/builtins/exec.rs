@@ -1,45 +1,82 @@
 // SPDX-FileCopyrightText: 2025 Ryuichi Ueda <ryuichiueda@gmail.com>
 // SPDX-License-Identifier: BSD-3-Clause
 
-use std::ffi::CString;
+use std::path::Path;
 
-use nix::{errno::Errno, unistd};
+use nix::{errno::Errno, sys::signal::Signal};
 
-use crate::{ShellCore, proc_ctrl};
+use crate::{ShellCore, proc_ctrl, signal, utils::exit};
+
+const USAGE: &str = "exec: usage: exec [-cl] [-a name] [command [argument ...]] [redirection ...]";
+
+fn usage_error(core: &mut ShellCore, msg: &str) -> i32 {
+    super::error_exit(2, "exec", msg, core);
+    eprintln!("{}", USAGE);
+    2
+}
 
 pub fn exec(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
     if core.db.flags.contains('r') {
         return super::error_exit(1, &args[0], "restricted", core);
     }
 
-    if args.len() == 1 {
+    let mut argv0 = None;
+    let mut clear_env = false;
+    let mut login = false;
+    let mut pos = 1;
+
+    while pos < args.len() && args[pos].starts_with("-") && args[pos] != "-" {
+        let opt = args[pos].clone();
+        pos += 1;
+        if opt == "--" {
+            break;
+        }
+
+        for (i, c) in opt.char_indices().skip(1) {
+            match c {
+                'c' => clear_env = true,
+                'l' => login = true,
+                'a' if i + 1 < opt.len() => {
+                    argv0 = Some(opt[i + 1..].to_string());
+                    break;
+                },
+                'a' if pos < args.len() => {
+                    argv0 = Some(args[pos].clone());
+                    pos += 1;
+                },
+                'a' => return usage_error(core, "-a: option requires an argument"),
+                _ => return usage_error(core, &format!("-{}: invalid option", c)),
+            }
+        }
+    }
+
+    if pos == args.len() {
         return 0;
     }
 
-    if core.db.flags.contains('i') || core.shopts.query("execfail") {
-        exec_command(&args[1..].to_vec(), core, &"".to_string())
-    } else {
-        proc_ctrl::exec_command(&args[1..].to_vec(), core, &"".to_string())
+    let words = args[pos..].to_vec();
+    let mut name = argv0.unwrap_or(words[0].clone());
+    if login {
+        name.insert(0, '-');
     }
-}
 
-fn exec_command(args: &Vec<String>, core: &mut ShellCore, fullpath: &String) -> i32 {
-    let cargs: Vec<CString> = args.iter().map(|a| CString::new(a.to_string()).unwrap()).collect();
-    let cfullpath = CString::new(fullpath.to_string()).unwrap();
+    signal::restore(Signal::SIGPIPE);
+    signal::restore(Signal::SIGTSTP);
+    let errno = proc_ctrl::replace_process(&words, &name, "", clear_env);
+    signal::ignore(Signal::SIGPIPE);
+    signal::ignore(Signal::SIGTSTP);
 
-    if !fullpath.is_empty() {
-        let _ = unistd::execv(&cfullpath, &cargs);
-    }
-    let result = unistd::execvp(&cargs[0], &cargs);
-
-    match result {
-        Err(Errno::E2BIG) => return super::error_exit(126, &args[0], "Arg list too long", core),
-        Err(Errno::EACCES) => return super::error_exit(126, &args[0], "cannot execute: Permission denied", core),
-        Err(Errno::ENOENT) => return super::error_exit(127, &args[0], "No such file or directory", core),
-        Err(e) => {
-            let msg = format!("{:?}", &e);
-            return super::error_exit(127, &args[0], &msg, core);
-        },
-        _ => return 127,
+    let (exit_status, msg) = match errno {
+        Errno::ENOENT => (127, "not found".to_string()),
+        Errno::E2BIG => (126, "Arg list too long".to_string()),
+        Errno::EACCES if Path::new(&words[0]).is_dir() => (126, "cannot execute: Is a directory".to_string()),
+        e => (126, format!("cannot execute: {}", e.desc())),
+    };
+    super::error_exit(exit_status, &args[0], &format!("{}: {}", &words[0], &msg), core);
+
+    if !core.db.flags.contains('i') && !core.shopts.query("execfail") {
+        core.db.exit_status = exit_status;
+        exit::normal(core);
     }
+    exit_status
 }
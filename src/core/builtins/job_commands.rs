@@ -1,19 +1,29 @@
 // SPDX-FileCopyrightText: 2024 Ryuichi Ueda <ryuichiueda@gmail.com>
 // SPDX-License-Identifier: BSD-3-Clause
 
-use std::{thread, time};
-
-use nix::{sys::signal::Signal, unistd, unistd::Pid};
-
-use crate::{ShellCore, core::JobEntry, signal, utils, utils::arg};
+use std::{
+    thread, time,
+    time::Instant,
+};
+
+use nix::{
+    errno::Errno,
+    sys::signal::Signal,
+    unistd,
+    unistd::Pid,
+};
+
+use crate::{
+    ShellCore,
+    core::JobEntry,
+    elements::io::pipe::Pipe,
+    error, proc_ctrl, signal,
+    utils::arg,
+};
 
 fn pid_to_array_pos(pid: i32, jobs: &Vec<JobEntry>) -> Option<usize> {
-    for i in 0..jobs.len() {
-        if jobs[i].pids[0].as_raw() == pid {
-            return Some(i);
-        }
-    }
-    None
+    let pid = Pid::from_raw(pid);
+    jobs.iter().position(|j| j.pids.contains(&pid))
 }
 
 fn jobid_to_pos(id: usize, jobs: &mut Vec<JobEntry>) -> Option<usize> {
@@ -117,146 +127,145 @@ pub fn fg(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
     }
 
     signal::ignore(Signal::SIGTTOU);
-
-    let mut exit_status = 1;
     if let Some(fd) = core.tty_fd.as_ref() {
-        if let Ok(_) = unistd::tcsetpgrp(fd, pgid) {
-            println!("{}", &core.job_table[pos].text);
-            core.job_table[pos].send_cont();
-            exit_status = core.job_table[pos].update_status(true, false).unwrap_or(1);
-
-            if let Ok(mypgid) = unistd::getpgid(Some(Pid::from_raw(0))) {
-                let _ = unistd::tcsetpgrp(fd, mypgid);
-            }
+        if unistd::tcsetpgrp(fd, pgid).is_err() {
+            signal::restore(Signal::SIGTTOU);
+            return 1;
         }
-    } else {
-        println!("{}", &core.job_table[pos].text);
-        core.job_table[pos].send_cont();
-        exit_status = core.job_table[pos].update_status(true, false).unwrap_or(1);
     }
-
-    remove(core, pos);
     signal::restore(Signal::SIGTTOU);
+
+    println!("{}", &core.job_table[pos].text);
+    core.job_table[pos].send_cont();
+    let exit_status = core.job_table[pos].update_status(true, false).unwrap_or(1);
+    proc_ctrl::restore_terminal(core, core.job_table[pos].exit_status() > 128);
+
+    if core.job_table[pos].is_finished() {
+        remove(core, pos);
+        return exit_status;
+    }
+
+    let id = core.job_table[pos].id;
+    core.job_table_priority.retain(|i| *i != id);
+    core.job_table_priority.insert(0, id);
+    println!();
+    core.job_table[pos].print(&core.job_table_priority, false);
+    core.job_table[pos].set_notified();
     exit_status
 }
 
 fn jobspec_to_array_pos(core: &mut ShellCore, com: &str, jobspec: &str) -> Option<usize> {
     let poss = jobspec_to_array_poss(core, jobspec);
-    if poss.is_empty() {
+    if poss.len() > 1 {
+        let name = jobspec.strip_prefix("%").unwrap_or(jobspec);
+        let msg = format!("{}: ambiguous job spec", name.strip_prefix("?").unwrap_or(name));
+        super::error_exit(1, com, &msg, core);
+    }
+    if poss.len() != 1 {
         let msg = format!("{}: no such job", &jobspec);
-        super::error_exit(127, &com, &msg, core);
-        return None;
-    } else if poss.len() > 1 {
-        let msg = format!("{}: ambiguous job spec", &jobspec[1..]);
-        super::error_exit(127, com, &msg, core);
+        super::error_exit(1, com, &msg, core);
         return None;
     }
 
     Some(poss[0])
 }
 
+/* %%, %+, % and %- for the current/previous job, %n for a job number,
+ * %?str for a substring of a command, %str for a prefix. The % can be omitted. */
 fn jobspec_to_array_poss(core: &mut ShellCore, jobspec: &str) -> Vec<usize> {
     if jobspec == "" {
         return (0..core.job_table.len()).collect();
     }
 
-    if core.job_table.is_empty() {
-        return vec![];
-    }
-
-    let s = &jobspec[1..];
-    let mut ans = vec![];
+    let s = jobspec.strip_prefix("%").unwrap_or(jobspec);
+    let priority = &core.job_table_priority;
+    let current = priority.first();
+    let previous = priority.get(1).or(current);
 
-    if let Ok(n) = s.parse::<usize>() {
-        for (i, job) in core.job_table.iter_mut().enumerate() {
-            if n == job.id {
-                ans.push(i);
-            }
-        }
-    } else if s == "%" || s == "+" {
-        for (i, job) in core.job_table.iter_mut().enumerate() {
-            if job.id == core.job_table_priority[0] {
-                ans.push(i);
-            }
-        }
-    } else if s == "-" {
-        for (i, job) in core.job_table.iter_mut().enumerate() {
-            if core.job_table_priority.len() < 2 {
-                if job.id == core.job_table_priority[0] {
-                    ans.push(i);
-                }
-            } else {
-                if job.id == core.job_table_priority[1] {
-                    ans.push(i);
-                }
-            }
-        }
-    } else if s.starts_with("?") {
-        for (i, job) in core.job_table.iter_mut().enumerate() {
-            if job.text.contains(&s[1..]) {
-                ans.push(i);
-            }
-        }
-    } else {
-        for (i, job) in core.job_table.iter_mut().enumerate() {
-            if job.text.starts_with(s) {
-                ans.push(i);
-            }
-        }
-    }
+    let matched = |job: &JobEntry| match s {
+        "" | "%" | "+" => current == Some(&job.id),
+        "-" => previous == Some(&job.id),
+        _ if s.chars().all(|c| c.is_ascii_digit()) => s.parse::<usize>() == Ok(job.id),
+        _ => match s.strip_prefix("?") {
+            Some(sub) => job.commands.iter().any(|c| c.contains(sub)),
+            None => job.text.starts_with(s),
+        },
+    };
 
-    ans
+    core.job_table.iter().enumerate().filter(|(_, j)| matched(j)).map(|(i, _)| i).collect()
 }
 
-pub fn jobs(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
-    let mut args = arg::dissolve_options(args);
-    if arg::consume_option("-n", &mut args) {
-        core.jobtable_print_status_change();
+const JOBS_USAGE: &str = "jobs: usage: jobs [-lnprs] [jobspec ...] or jobs -x command [args]";
+
+fn jobs_execute(core: &mut ShellCore, words: &[String]) -> i32 {
+    if words.is_empty() {
         return 0;
     }
 
-    let jobspecs = arg::consume_starts_with("%", &mut args);
-    let jobspec = match jobspecs.last() {
-        Some(s) => s.clone(),
-        None => String::new(),
-    };
-
-    if core.job_table.is_empty() && jobspec == "" {
-        return 0;
+    let mut words = words.to_vec();
+    for w in words.iter_mut().filter(|w| w.starts_with("%")) {
+        if let Some(pos) = jobspec_to_array_pos(core, "jobs", w) {
+            *w = core.job_table[pos].pids[0].to_string();
+        }
     }
 
-    let poss = jobspec_to_array_poss(core, &jobspec);
+    super::run_simple_command(core, words, &mut Pipe::new("".to_string()))
+}
 
-    if poss.is_empty() {
-        let msg = format!("{}: no such job", &jobspec);
-        return super::error_exit(127, "jobs", &msg, core);
-    }
-    if poss.len() > 1 && !jobspec.is_empty() {
-        let msg = format!("{}: ambiguous job spec", &jobspec[1..]);
-        super::error_exit(127, "jobs", &msg, core);
-        let msg = format!("{}: no such job", &jobspec);
-        return super::error_exit(127, "jobs", &msg, core);
-    }
+pub fn jobs(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
+    let mut form = ' ';
+    let mut state = ' ';
+    let mut execute = false;
+    let mut pos = 1;
+
+    while pos < args.len() && args[pos].starts_with("-") && args[pos] != "-" {
+        pos += 1;
+        if args[pos - 1] == "--" {
+            break;
+        }
 
-    if arg::consume_option("-p", &mut args) {
-        for id in poss {
-            core.job_table[id].print_p();
+        for c in args[pos - 1][1..].chars() {
+            match c {
+                'l' | 'p' | 'n' => form = c,
+                'r' | 's' => state = c,
+                'x' if form != ' ' => {
+                    return super::error_exit(1, &args[0], "no other options allowed with `-x'", core);
+                },
+                'x' => execute = true,
+                _ => {
+                    let msg = format!("-{}: invalid option", c);
+                    super::error_exit(2, &args[0], &msg, core);
+                    eprintln!("{}", JOBS_USAGE);
+                    return 2;
+                },
+            }
         }
-        return 0;
     }
 
-    if !jobspec.is_empty() {
-        let l_opt = arg::consume_option("-l", &mut args);
-        let r_opt = arg::consume_option("-r", &mut args);
-        let s_opt = arg::consume_option("-s", &mut args);
-        if core.job_table[poss[0]].print(&core.job_table_priority, l_opt, r_opt, s_opt, true) {
-            remove(core, poss[0]);
+    if execute {
+        return jobs_execute(core, &args[pos..]);
+    }
+
+    let mut exit_status = 0;
+    let mut targets = vec![];
+    if pos == args.len() {
+        targets = jobspec_to_array_poss(core, "");
+        targets.retain(|p| match state {
+            'r' => core.job_table[*p].is_running(),
+            's' => core.job_table[*p].display_status == "Stopped",
+            _ => true,
+        });
+    }
+
+    for spec in &args[pos..] {
+        match jobspec_to_array_pos(core, "jobs", spec) {
+            Some(p) => targets.push(p),
+            None => exit_status = 1,
         }
-        return 0;
     }
 
-    print(core, &mut args);
-    0
+    print(core, &targets, form);
+    exit_status
 }
 
 fn get_priority(core: &mut ShellCore, pos: usize) -> usize {
@@ -270,37 +279,86 @@ fn get_priority(core: &mut ShellCore, pos: usize) -> usize {
     core.job_table.len()
 }
 
-fn print(core: &mut ShellCore, args: &mut Vec<String>) {
-    let l_opt = arg::consume_option("-l", args);
-    let r_opt = arg::consume_option("-r", args);
-    let s_opt = arg::consume_option("-s", args);
-
+fn print(core: &mut ShellCore, targets: &Vec<usize>, form: char) {
     let mut rem = vec![];
-    for id in 0..core.job_table.len() {
-        if core.job_table[id].print(&core.job_table_priority, l_opt, r_opt, s_opt, true) {
-            rem.push(id);
+    for pos in targets {
+        let job = &mut core.job_table[*pos];
+        match form {
+            'p' => {
+                job.print_p();
+                continue;
+            },
+            'n' if !job.is_changed() => continue,
+            _ => job.print(&core.job_table_priority, form == 'l'),
+        }
+
+        job.set_notified();
+        if job.is_finished() {
+            rem.push(*pos);
         }
     }
 
+    rem.sort();
+    rem.dedup();
     for pos in rem.into_iter().rev() {
         remove(core, pos);
     }
 }
 
 fn remove(core: &mut ShellCore, pos: usize) {
-    let job_id = core.job_table[pos].id;
-    core.job_table.remove(pos);
-    core.job_table_priority.retain(|id| *id != job_id);
+    core.jobtable_remove(pos);
 }
 
-fn wait_jobspec(core: &mut ShellCore, com: &str, jobspec: &str, var_name: &Option<String>, f_opt: bool) -> (i32, bool) {
+/* 128 + SIGALRM, as if an alarm had interrupted wait */
+const WAIT_TIMEOUT: i32 = 142;
+
+fn wait_ready(job: &JobEntry, f_opt: bool) -> bool {
+    job.is_finished() || (!f_opt && job.display_status == "Stopped")
+}
+
+fn poll_job(core: &mut ShellCore, pos: usize, f_opt: bool, deadline: Instant) -> bool {
+    loop {
+        let _ = core.job_table[pos].update_status(false, false);
+        if wait_ready(&core.job_table[pos], f_opt) {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        thread::sleep(time::Duration::from_millis(10));
+    }
+}
+
+fn set_wait_var(core: &mut ShellCore, var_name: &Option<String>, pid: &str) {
+    if let Some(var) = var_name {
+        core.db.unset(var);
+        if let Err(e) = core.db.set_param(var, pid, None) {
+            e.print(core);
+        }
+    }
+}
+
+fn wait_jobspec(
+    core: &mut ShellCore,
+    com: &str,
+    jobspec: &str,
+    var_name: &Option<String>,
+    f_opt: bool,
+    deadline: Option<Instant>,
+) -> (i32, bool) {
     match jobspec_to_array_pos(core, com, jobspec) {
-        Some(pos) => wait_a_job(core, pos, var_name, f_opt),
+        Some(pos) => wait_a_job(core, pos, var_name, f_opt, deadline),
         None => return (127, false),
     }
 }
 
-fn wait_next(core: &mut ShellCore, ids: &Vec<usize>, var_name: &Option<String>, f_opt: bool) -> (i32, bool) {
+fn wait_next(
+    core: &mut ShellCore,
+    ids: &Vec<usize>,
+    var_name: &Option<String>,
+    f_opt: bool,
+    deadline: Option<Instant>,
+) -> (i32, bool) {
     if core.job_table_priority.is_empty() {
         return (127, false);
     }
@@ -319,14 +377,14 @@ fn wait_next(core: &mut ShellCore, ids: &Vec<usize>, var_name: &Option<String>,
             }
 
             if let Ok(es) = job.update_status(false, true) {
-                if job.display_status == "Done"
-                    || job.display_status == "Killed"
-                    || (job.display_status == "Stopped" && !f_opt)
-                {
-                    exit_status = es;
+                if wait_ready(job, f_opt) {
+                    exit_status = match job.is_finished() {
+                        true => job.exit_status(),
+                        false => es,
+                    };
                     drop = i;
                     end = true;
-                    remove_job = job.display_status == "Done" || job.display_status == "Killed";
+                    remove_job = job.is_finished();
                     pid = job.pids[0].to_string();
                     break;
                 }
@@ -336,43 +394,70 @@ fn wait_next(core: &mut ShellCore, ids: &Vec<usize>, var_name: &Option<String>,
         if end {
             break;
         }
-    }
-
-    if let Some(var) = var_name {
-        core.db.unset(&var);
-        if let Err(e) = core.db.set_param(&var, &pid, None) {
-            e.print(core);
+        if deadline.is_some_and(|d| Instant::now() >= d) {
+            return (WAIT_TIMEOUT, false);
         }
     }
 
+    set_wait_var(core, var_name, &pid);
+
     if remove_job {
         remove(core, drop);
     }
     (exit_status, true)
 }
 
-fn wait_pid(core: &mut ShellCore, pid: i32, var_name: &Option<String>, f_opt: bool) -> (i32, bool) {
-    match pid_to_array_pos(pid, &core.job_table) {
-        Some(i) => wait_a_job(core, i, var_name, f_opt),
-        None => (127, false),
+fn wait_pid(
+    core: &mut ShellCore,
+    pid: i32,
+    var_name: &Option<String>,
+    f_opt: bool,
+    deadline: Option<Instant>,
+) -> (i32, bool) {
+    if let Some(i) = pid_to_array_pos(pid, &core.job_table) {
+        return wait_a_job(core, i, var_name, f_opt, deadline);
+    }
+
+    match core.jobtable_saved_status(Pid::from_raw(pid)) {
+        Some(es) => {
+            set_wait_var(core, var_name, &pid.to_string());
+            (es, true)
+        },
+        None => {
+            let msg = format!("pid {} is not a child of this shell", pid);
+            (super::error_exit(127, "wait", &msg, core), false)
+        },
     }
 }
 
-fn wait_a_job(core: &mut ShellCore, pos: usize, var_name: &Option<String>, f_opt: bool) -> (i32, bool) {
+fn wait_a_job(
+    core: &mut ShellCore,
+    pos: usize,
+    var_name: &Option<String>,
+    f_opt: bool,
+    deadline: Option<Instant>,
+) -> (i32, bool) {
     if core.job_table.len() < pos {
         return (super::error_exit(127, "wait", "invalpos jobpos", core), false);
     }
 
+    if let Some(d) = deadline {
+        if !poll_job(core, pos, f_opt, d) {
+            return (WAIT_TIMEOUT, false);
+        }
+    }
+
     let pid = core.job_table[pos].pids[0].to_string();
 
-    let ans = match core.job_table[pos].update_status(true, false) {
+    let job = &mut core.job_table[pos];
+    let result = match job.is_finished() {
+        true => Ok(job.exit_status()),
+        false => job.update_status(true, false).map(|_| job.exit_status()),
+    };
+
+    let ans = match result {
         Ok(n) => {
-            if let Some(var) = var_name {
-                core.db.unset(&var);
-                if let Err(e) = core.db.set_param(&var, &pid, None) {
-                    e.print(core);
-                }
-            }
+            set_wait_var(core, var_name, &pid);
             (n, true)
         },
         Err(e) => {
@@ -382,40 +467,58 @@ fn wait_a_job(core: &mut ShellCore, pos: usize, var_name: &Option<String>, f_opt
     };
 
     if f_opt && core.job_table[pos].display_status == "Stopped" {
-        wait_a_job(core, pos, var_name, f_opt)
-    } else {
+        wait_a_job(core, pos, var_name, f_opt, deadline)
+    } else if core.job_table[pos].is_finished() {
         remove(core, pos);
         ans
+    } else {
+        ans
     }
 }
 
-fn wait_arg_job(core: &mut ShellCore, com: &str, arg: &String, var_name: &Option<String>, f_opt: bool) -> (i32, bool) {
+fn wait_arg_job(
+    core: &mut ShellCore,
+    com: &str,
+    arg: &String,
+    var_name: &Option<String>,
+    f_opt: bool,
+    deadline: Option<Instant>,
+) -> (i32, bool) {
     if arg.starts_with("%") {
-        return wait_jobspec(core, com, &arg, &var_name, f_opt);
+        return wait_jobspec(core, com, &arg, &var_name, f_opt, deadline);
     }
 
     if let Ok(pid) = arg.parse::<i32>() {
-        return wait_pid(core, pid, &var_name, f_opt);
+        return wait_pid(core, pid, &var_name, f_opt, deadline);
     }
 
-    (127, false)
+    let msg = format!("`{}': not a pid or valid job spec", arg);
+    (super::error_exit(1, com, &msg, core), false)
 }
 
-fn wait_all(core: &mut ShellCore) -> i32 {
-    let mut exit_status = 0;
+fn wait_all(core: &mut ShellCore, deadline: Option<Instant>) -> i32 {
     let mut remove_list = vec![];
     for pos in 0..core.job_table.len() {
+        if let Some(d) = deadline {
+            if !poll_job(core, pos, true, d) {
+                return WAIT_TIMEOUT;
+            }
+        }
+
+        if core.job_table[pos].is_finished() {
+            remove_list.push(pos);
+            continue;
+        }
+
         match core.job_table[pos].update_status(true, false) {
-            Ok(n) => {
-                if core.job_table[pos].display_status == "Done" || core.job_table[pos].display_status == "Killed" {
+            Ok(_) => {
+                if core.job_table[pos].is_finished() {
                     remove_list.push(pos);
                 }
-                exit_status = n;
             },
             Err(e) => {
                 e.print(core);
-                exit_status = 1;
-                break;
+                return 1;
             },
         }
     }
@@ -424,14 +527,20 @@ fn wait_all(core: &mut ShellCore) -> i32 {
         remove(core, pos);
     }
 
-    return exit_status;
+    0
 }
 
-fn wait_n(core: &mut ShellCore, args: &mut Vec<String>, var_name: &Option<String>, f_opt: bool) -> i32 {
+fn wait_n(
+    core: &mut ShellCore,
+    args: &mut Vec<String>,
+    var_name: &Option<String>,
+    f_opt: bool,
+    deadline: Option<Instant>,
+) -> i32 {
     let mut jobs = arg::consume_with_subsequents("-n", args);
     jobs.remove(0);
     if jobs.is_empty() {
-        return wait_next(core, &vec![], &var_name, f_opt).0;
+        return wait_next(core, &vec![], &var_name, f_opt, deadline).0;
     }
 
     let mut ids = vec![];
@@ -452,10 +561,13 @@ fn wait_n(core: &mut ShellCore, args: &mut Vec<String>, var_name: &Option<String
 
     for _ in 0..ids.len() {
         let tmp = match ans {
-            -1 => wait_next(core, &ids, &var_name, f_opt),
-            _ => wait_next(core, &ids, &None, f_opt),
+            -1 => wait_next(core, &ids, &var_name, f_opt, deadline),
+            _ => wait_next(core, &ids, &None, f_opt, deadline),
         };
 
+        if tmp == (WAIT_TIMEOUT, false) {
+            return WAIT_TIMEOUT;
+        }
         if tmp.1 == true && ans == -1 {
             ans = tmp.0;
         }
@@ -468,101 +580,295 @@ pub fn wait(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
         super::error_exit(127, &args[0], "called from subshell", core);
     }
 
-    if args.len() <= 1 {
-        return wait_all(core);
-    }
-
     let mut args = arg::dissolve_options(args);
     let var_name = arg::consume_with_next_arg("-p", &mut args);
     let f_opt = arg::consume_option("-f", &mut args);
+    let deadline = match arg::consume_with_next_arg("-t", &mut args) {
+        Some(t) => match t.parse::<f64>() {
+            Ok(sec) if sec.is_finite() && sec >= 0.0 => Some(Instant::now() + time::Duration::from_secs_f64(sec)),
+            _ => {
+                let msg = format!("{}: invalid timeout specification", &t);
+                return super::error_exit(1, &args[0], &msg, core);
+            },
+        },
+        None => None,
+    };
 
-    if args.len() > 1 && args[1] == "-n" {
-        return wait_n(core, &mut args, &var_name, f_opt);
+    if args.len() <= 1 {
+        return wait_all(core, deadline);
     }
 
-    if args.len() > 1 {
-        return wait_arg_job(core, &args[0], &args[1], &var_name, f_opt).0;
+    if args[1] == "-n" {
+        return wait_n(core, &mut args, &var_name, f_opt, deadline);
     }
-    1
+
+    let mut exit_status = 0;
+    for a in &args[1..] {
+        exit_status = wait_arg_job(core, &args[0], a, &var_name, f_opt, deadline).0;
+        if exit_status == WAIT_TIMEOUT {
+            break;
+        }
+    }
+    exit_status
 }
 
-// TODO: implement original kill
-pub fn kill(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
-    // let mut args = arg::dissolve_options(args);
-    let path = utils::get_command_path(&args[0], core);
+const KILL_USAGE: &str =
+    "kill: usage: kill [-s sigspec | -n signum | -sigspec] pid | jobspec ... or kill -l [sigspec]";
 
-    match path.is_empty() {
-        true => return 1,
-        false => args[0] = path,
+fn send_signal(pid: i32, sig: i32) -> Result<(), Errno> {
+    let pid = Pid::from_raw(pid);
+    match Signal::try_from(sig) {
+        Ok(s) => nix::sys::signal::kill(pid, s),
+        Err(_) if sig == 0 => nix::sys::signal::kill(pid, None),
+        Err(_) => Errno::result(unsafe { nix::libc::kill(pid.as_raw(), sig) }).map(drop),
     }
+}
 
-    if args.len() >= 3 && args[2].starts_with("%") {
-        match jobspec_to_array_pos(core, &args[0], &args[2]) {
-            Some(id) => args[2] = core.job_table[id].pids[0].to_string(),
-            None => return 1,
+fn kill_list(core: &mut ShellCore, args: &[String]) -> i32 {
+    if args.is_empty() {
+        let mut table = String::new();
+        for (i, n) in signal::numbers().iter().enumerate() {
+            table += &format!("{:2}) {}", n, signal::to_name(*n).unwrap());
+            table += if i % 5 == 4 { "\n" } else { "\t" };
+        }
+        if !table.ends_with("\n") {
+            table += "\n";
         }
+        print!("{}", table);
+        return 0;
     }
 
-    let com = args[0].to_string();
-    for arg in args.iter_mut() {
-        if arg == "-n" {
-            *arg = "-s".to_string();
-        }
-        if arg.starts_with("%") {
-            if let Some(pos) = jobspec_to_array_pos(core, &com, &arg) {
-                *arg = core.job_table[pos].pids[0].to_string();
-            } else {
-                let msg = format!("{}: no such job", &arg);
-                return super::error_exit(127, "jobs", &msg, core);
-            }
+    let mut exit_status = 0;
+    for a in args {
+        let name = match a.parse::<i32>() {
+            Ok(0) => Some("EXIT".to_string()),
+            Ok(n) => signal::to_name(if n > 128 { n - 128 } else { n }).map(|s| s[3..].to_string()),
+            Err(_) => signal::to_num(a).map(|n| n.to_string()),
+        };
+
+        match name {
+            Some(s) => println!("{}", s),
+            None => {
+                let msg = format!("{}: invalid signal specification", a);
+                exit_status = super::error_exit(1, "kill", &msg, core);
+            },
         }
     }
+    exit_status
+}
+
+fn kill_job(core: &mut ShellCore, jobspec: &str, sig: i32) -> i32 {
+    let pos = match jobspec_to_array_pos(core, "kill", jobspec) {
+        Some(p) => p,
+        None => return 1,
+    };
+
+    let job = &core.job_table[pos];
+    let pgid = job.solve_pgid();
+    let targets: Vec<i32> = match pgid.as_raw() > 0 && pgid != unistd::getpgrp() {
+        true => vec![-pgid.as_raw()],
+        false => job.pids.iter().map(|p| p.as_raw()).collect(),
+    };
+    let stopped = job.display_status == "Stopped";
 
-    args.insert(0, "eval".to_string());
-    super::eval(core, args)
+    for t in targets {
+        if let Err(e) = send_signal(t, sig) {
+            let msg = format!("({}) - {}", t.abs(), e.desc());
+            return super::error_exit(1, "kill", &msg, core);
+        }
+        if stopped && (sig == Signal::SIGTERM as i32 || sig == Signal::SIGHUP as i32) {
+            let _ = send_signal(t, Signal::SIGCONT as i32);
+        }
+    }
+    0
 }
 
-pub fn disown(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
-    let mut args = arg::dissolve_options(args);
-    let h_opt = arg::consume_option("-h", &mut args);
-    let _r_opt = arg::consume_option("-r", &mut args); //TODO: implement
+pub fn kill(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
+    let mut sig = Signal::SIGTERM as i32;
+    let mut saw_signal = false;
+    let mut listing = false;
+    let mut pos = 1;
+
+    while pos < args.len() && args[pos].starts_with("-") {
+        let word = args[pos].clone();
+        pos += 1;
+        match word.as_str() {
+            "-l" | "-L" => listing = true,
+            "-s" | "-n" => {
+                let spec = match args.get(pos) {
+                    Some(a) => a.clone(),
+                    None => {
+                        let msg = format!("{}: option requires an argument", &word);
+                        return super::error_exit(1, "kill", &msg, core);
+                    },
+                };
+                pos += 1;
+                sig = match signal::to_num(&spec) {
+                    Some(n) => n,
+                    None => {
+                        let msg = format!("{}: invalid signal specification", &spec);
+                        return super::error_exit(1, "kill", &msg, core);
+                    },
+                };
+                saw_signal = true;
+            },
+            "--" => break,
+            _ if !saw_signal && !listing => {
+                sig = match signal::to_num(&word[1..]) {
+                    Some(n) => n,
+                    None => {
+                        let msg = format!("{}: invalid signal specification", &word[1..]);
+                        return super::error_exit(1, "kill", &msg, core);
+                    },
+                };
+                saw_signal = true;
+            },
+            _ => {
+                pos -= 1;
+                break;
+            },
+        }
+    }
 
-    if args.len() == 1 {
-        let ids = jobspec_to_array_poss(core, "%%");
+    if listing {
+        return kill_list(core, &args[pos..]);
+    }
+    if pos >= args.len() {
+        eprintln!("{}", KILL_USAGE);
+        return 2;
+    }
 
-        if ids.len() == 1 {
-            core.job_table.remove(ids[0]);
-            core.job_table_priority.remove(0);
-            return 0;
+    // As in Bash, kill succeeds when any of the targets gets the signal.
+    let mut succeeded = false;
+    for a in &args[pos..] {
+        if a.starts_with("%") {
+            succeeded |= kill_job(core, a, sig) == 0;
+            continue;
         }
 
-        return 1;
+        let pid = match a.parse::<i32>() {
+            Ok(pid) => pid,
+            Err(_) => {
+                let msg = format!("{}: arguments must be process or job IDs", &a);
+                super::error_exit(1, "kill", &msg, core);
+                continue;
+            },
+        };
+        match send_signal(pid, sig) {
+            Ok(_) => succeeded = true,
+            Err(e) => {
+                let msg = format!("({}) - {}", pid, e.desc());
+                super::error_exit(1, "kill", &msg, core);
+            },
+        }
     }
 
-    if args.len() == 2 && args[1] == "-a" {
-        core.job_table.clear();
-        core.job_table_priority.clear();
-        return 0;
+    match succeeded {
+        true => 0,
+        false => 1,
     }
+}
 
-    for a in &args[1..] {
-        if a.starts_with("-") {
-            let msg = format!("{}: invalid option", &a);
-            super::error_exit(127, &args[0], &msg, core);
-            eprintln!("disown: usage: disown [-h] [-ar] [jobspec ... | pid ...]");
-            return 127;
+const DISOWN_USAGE: &str = "disown: usage: disown [-h] [-ar] [jobspec ... | pid ...]";
+
+fn disown_target(core: &mut ShellCore, arg: &str) -> Option<usize> {
+    if let Ok(pid) = arg.parse::<i32>() {
+        let pid = Pid::from_raw(pid);
+        let pos = core.job_table.iter().position(|j| j.pids.contains(&pid));
+        if pos.is_none() {
+            super::error_exit(1, "disown", &format!("{}: no such job", arg), core);
         }
+        return pos;
     }
 
-    for a in &args[1..] {
-        if let Some(pos) = jobspec_to_array_pos(core, &args[0], &a) {
-            if h_opt {
-                // TODO: to make each job doesn't stop by SIGHUP
-            } else {
-                remove(core, pos);
+    jobspec_to_array_pos(core, "disown", arg)
+}
+
+fn disown_job(core: &mut ShellCore, pos: usize, h_opt: bool) {
+    let job = &mut core.job_table[pos];
+    if h_opt {
+        job.no_hup = true;
+        return;
+    }
+
+    if job.display_status == "Stopped" {
+        let msg = format!("warning: deleting stopped job {} with process group {}", job.id, job.solve_pgid());
+        error::print(&msg, core);
+    }
+    remove(core, pos);
+}
+
+pub fn disown(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
+    let mut args = arg::dissolve_options(args);
+    let h_opt = arg::consume_option("-h", &mut args);
+    let a_opt = arg::consume_option("-a", &mut args);
+    let r_opt = arg::consume_option("-r", &mut args);
+
+    if args.len() > 1 && args[1] == "--" {
+        args.remove(1);
+    } else if let Some(a) = args[1..].iter().find(|a| a.starts_with("-") && a.len() > 1) {
+        let msg = format!("{}: invalid option", &a);
+        super::error_exit(2, &args[0], &msg, core);
+        eprintln!("{}", DISOWN_USAGE);
+        return 2;
+    }
+
+    if args.len() == 1 && (a_opt || r_opt) {
+        let mut pos = core.job_table.len();
+        while pos > 0 {
+            pos -= 1;
+            if !r_opt || core.job_table[pos].display_status == "Running" {
+                disown_job(core, pos, h_opt);
             }
         }
+        return 0;
     }
 
-    0
+    if args.len() == 1 {
+        return match jobspec_to_array_poss(core, "%+").first() {
+            Some(pos) => {
+                disown_job(core, *pos, h_opt);
+                0
+            },
+            None => super::error_exit(1, &args[0], "current: no such job", core),
+        };
+    }
+
+    let mut exit_status = 0;
+    for a in &args[1..] {
+        match disown_target(core, a) {
+            Some(pos) => disown_job(core, pos, h_opt),
+            None => exit_status = 1,
+        }
+    }
+    exit_status
+}
+
+pub fn suspend(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
+    let mut args = arg::dissolve_options(args);
+    let force = arg::consume_option("-f", &mut args);
+
+    if args.len() > 1 && args[1] == "--" {
+        args.remove(1);
+    } else if args.len() > 1 && args[1].starts_with("-") {
+        let msg = format!("{}: invalid option", &args[1]);
+        super::error_exit(2, &args[0], &msg, core);
+        eprintln!("suspend: usage: suspend [-f]");
+        return 2;
+    }
+
+    if !core.db.flags.contains('m') {
+        return super::error_exit(1, &args[0], "cannot suspend: no job control", core);
+    }
+    if args.len() > 1 {
+        return super::error_exit(1, &args[0], "too many arguments", core);
+    }
+    if !force && core.shopts.query("login_shell") {
+        return super::error_exit(1, &args[0], "cannot suspend a login shell", core);
+    }
+
+    match nix::sys::signal::killpg(unistd::getpgrp(), Signal::SIGSTOP) {
+        Ok(()) => 0,
+        Err(e) => super::error_exit(1, &args[0], e.desc(), core),
+    }
 }
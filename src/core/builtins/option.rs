@@ -56,6 +56,7 @@ pub fn set_short_options(core: &mut ShellCore, args: &mut Vec<String>) {
         ('C', "noclobber"),
         ('a', "allexport"),
         ('B', "braceexpand"),
+        ('b', "notify"),
         ('u', ""),
         ('e', ""),
         ('r', ""),
@@ -125,11 +126,14 @@ pub fn set(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
             core.options.print_all(positive);
             return 0;
         } else {
-            if args[2] == "monitor" {
-                if positive && !core.db.flags.contains('m') {
-                    core.db.flags.push('m');
+            for (short, long) in [('m', "monitor"), ('b', "notify")] {
+                if args[2] != long {
+                    continue;
+                }
+                if positive && !core.db.flags.contains(short) {
+                    core.db.flags.push(short);
                 } else if !positive {
-                    core.db.flags.retain(|f| f != 'm');
+                    core.db.flags.retain(|f| f != short);
                 }
             }
 
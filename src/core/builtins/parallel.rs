@@ -0,0 +1,322 @@
+// SPDX-FileCopyrightText: 2026 Ryuichi Ueda <ryuichiueda@gmail.com>
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{Read, Write},
+    os::fd::{AsFd, AsRawFd, IntoRawFd, OwnedFd},
+    sync::atomic::Ordering::Relaxed,
+    thread,
+};
+
+use nix::{
+    errno::Errno,
+    poll,
+    poll::{PollFd, PollFlags, PollTimeout},
+    sys::{
+        signal,
+        signal::Signal,
+        wait::WaitStatus,
+    },
+    unistd,
+    unistd::{ForkResult, Pid},
+};
+
+use crate::{
+    ShellCore,
+    core::JobEntry,
+    elements::{io, io::pipe::Pipe},
+    error::exec::ExecError,
+    proc_ctrl,
+    utils::exit,
+};
+
+const USAGE: &str = "sush_parallel: usage: sush_parallel [-k] [-j jobs] command [arg ...] [::: item ...]";
+
+struct Worker {
+    index:   usize,
+    job_id:  usize,
+    pid:     Pid,
+    out:     Option<OwnedFd>,
+    err:     Option<OwnedFd>,
+    out_buf: Vec<u8>,
+    err_buf: Vec<u8>,
+    status:  i32,
+}
+
+impl Worker {
+    fn is_closed(&self) -> bool {
+        self.out.is_none() && self.err.is_none()
+    }
+
+    fn flush(&self) {
+        let mut stdout = std::io::stdout();
+        let _ = stdout.write_all(&self.out_buf);
+        let _ = stdout.flush();
+        let mut stderr = std::io::stderr();
+        let _ = stderr.write_all(&self.err_buf);
+        let _ = stderr.flush();
+    }
+}
+
+/* {} in the arguments is replaced with an item. Without {}, the item is appended. */
+fn command_words(template: &[String], item: &str) -> Vec<String> {
+    match template.iter().any(|w| w.contains("{}")) {
+        true => template.iter().map(|w| w.replace("{}", item)).collect(),
+        false => {
+            let mut words = template.to_vec();
+            words.push(item.to_string());
+            words
+        },
+    }
+}
+
+fn read_items() -> Vec<String> {
+    let mut buf = vec![];
+    let _ = std::io::stdin().read_to_end(&mut buf);
+    String::from_utf8_lossy(&buf).lines().map(|s| s.to_string()).collect()
+}
+
+fn run_worker(core: &mut ShellCore, words: Vec<String>, out: OwnedFd, err: OwnedFd) -> ! {
+    io::replace(out.into_raw_fd(), 1);
+    io::replace(err.into_raw_fd(), 2);
+    if let Ok(null) = File::open("/dev/null") {
+        io::replace(null.into_raw_fd(), 0);
+    }
+
+    let mut pipe = Pipe::new(String::new());
+    pipe.pgid = unistd::getpgrp();
+    super::run_simple_command(core, words, &mut pipe);
+    exit::normal(core)
+}
+
+fn spawn(core: &mut ShellCore, words: Vec<String>, index: usize) -> Result<Worker, ExecError> {
+    let (out_recv, out_send) = unistd::pipe()?;
+    let (err_recv, err_send) = unistd::pipe()?;
+    let pgid = match core.is_subshell {
+        true => unistd::getpgrp(),
+        false => Pid::from_raw(0),
+    };
+
+    match unsafe { unistd::fork()? } {
+        ForkResult::Child => {
+            core.initialize_as_subshell(Pid::from_raw(0), pgid);
+            drop(out_recv);
+            drop(err_recv);
+            run_worker(core, words, out_send, err_send)
+        },
+        ForkResult::Parent { child } => {
+            proc_ctrl::set_pgid(core, child, pgid);
+
+            let job_id = core.generate_new_job_id();
+            let mut entry =
+                JobEntry::new(vec![Some(child)], &vec![WaitStatus::StillAlive], &words.join(" "), "Running", job_id);
+            if !core.options.query("monitor") {
+                entry.no_control = true;
+            }
+            core.jobtable_forget_saved_status(&[child]);
+            core.job_table_priority.insert(0, job_id);
+            core.job_table.push(entry);
+
+            Ok(Worker {
+                index,
+                job_id,
+                pid: child,
+                out: Some(out_recv),
+                err: Some(err_recv),
+                out_buf: vec![],
+                err_buf: vec![],
+                status: 0,
+            })
+        },
+    }
+}
+
+fn collect_output(workers: &mut [Worker]) {
+    let mut ready = vec![];
+    {
+        let mut targets = vec![];
+        let mut fds = vec![];
+        for (i, w) in workers.iter().enumerate() {
+            for (is_err, fd) in [(false, &w.out), (true, &w.err)] {
+                if let Some(fd) = fd {
+                    targets.push((i, is_err));
+                    fds.push(PollFd::new(fd.as_fd(), PollFlags::POLLIN));
+                }
+            }
+        }
+
+        match poll::poll(&mut fds, PollTimeout::from(50u8)) {
+            Ok(n) if n > 0 => {},
+            _ => return,
+        }
+
+        for (target, fd) in targets.into_iter().zip(fds.iter()) {
+            if fd.revents().is_some_and(|r| !r.is_empty()) {
+                ready.push(target);
+            }
+        }
+    }
+
+    let mut buf = [0u8; 4096];
+    for (i, is_err) in ready {
+        let w = &mut workers[i];
+        let fd = match is_err {
+            true => &mut w.err,
+            false => &mut w.out,
+        };
+
+        let raw = fd.as_ref().unwrap().as_raw_fd();
+        match unistd::read(raw, &mut buf) {
+            Ok(0) => *fd = None,
+            Ok(n) => match is_err {
+                true => w.err_buf.extend_from_slice(&buf[..n]),
+                false => w.out_buf.extend_from_slice(&buf[..n]),
+            },
+            Err(Errno::EINTR) | Err(Errno::EAGAIN) => {},
+            Err(_) => *fd = None,
+        }
+    }
+}
+
+fn reap(core: &mut ShellCore, workers: &mut Vec<Worker>) -> Vec<Worker> {
+    let mut finished = vec![];
+    let mut i = 0;
+    while i < workers.len() {
+        if !workers[i].is_closed() {
+            i += 1;
+            continue;
+        }
+
+        let pos = core.job_table.iter().position(|j| j.id == workers[i].job_id && j.pids.contains(&workers[i].pid));
+        match pos {
+            Some(pos) => {
+                let _ = core.job_table[pos].update_status(false, false);
+                if !core.job_table[pos].is_finished() {
+                    i += 1;
+                    continue;
+                }
+                workers[i].status = core.job_table[pos].exit_status();
+                core.jobtable_remove(pos);
+            },
+            None => workers[i].status = core.jobtable_saved_status(workers[i].pid).unwrap_or(0),
+        }
+
+        finished.push(workers.remove(i));
+    }
+    finished
+}
+
+fn terminate(core: &mut ShellCore, workers: &[Worker]) {
+    for w in workers {
+        match core.job_table.iter().find(|j| j.id == w.job_id) {
+            Some(job) if job.solve_pgid() != unistd::getpgrp() => {
+                let _ = signal::killpg(job.solve_pgid(), Signal::SIGTERM);
+            },
+            _ => {
+                let _ = signal::kill(w.pid, Signal::SIGTERM);
+            },
+        }
+    }
+}
+
+pub fn parallel(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
+    let mut max = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let mut keep_order = false;
+    let mut pos = 1;
+
+    while pos < args.len() && args[pos].starts_with("-") && args[pos] != "-" {
+        let jobs = match args[pos].as_str() {
+            "--" => {
+                pos += 1;
+                break;
+            },
+            "-k" => None,
+            "-j" => {
+                pos += 1;
+                Some(args.get(pos).cloned().unwrap_or_default())
+            },
+            a if a.starts_with("-j") => Some(a[2..].to_string()),
+            a => {
+                let msg = format!("{}: invalid option", a);
+                super::error_exit(2, &args[0], &msg, core);
+                eprintln!("{}", USAGE);
+                return 2;
+            },
+        };
+
+        match jobs {
+            None => keep_order = true,
+            Some(n) => match n.parse::<usize>() {
+                Ok(n) if n > 0 => max = n,
+                _ => {
+                    let msg = format!("{}: invalid number of jobs", n);
+                    return super::error_exit(1, &args[0], &msg, core);
+                },
+            },
+        }
+        pos += 1;
+    }
+
+    let rest = &args[pos..];
+    if rest.is_empty() || rest[0] == ":::" {
+        eprintln!("{}", USAGE);
+        return 2;
+    }
+
+    let (template, items) = match rest.iter().position(|a| a == ":::") {
+        Some(p) => (rest[..p].to_vec(), rest[p + 1..].to_vec()),
+        None => (rest.to_vec(), read_items()),
+    };
+
+    let backup = core.tty_fd.take();
+    let mut workers = vec![];
+    let mut done = BTreeMap::new();
+    let mut next = 0;
+    let mut worst = 0;
+
+    loop {
+        if core.sigint.load(Relaxed) {
+            terminate(core, &workers);
+            worst = 130;
+            break;
+        }
+
+        while workers.len() < max && next < items.len() {
+            match spawn(core, command_words(&template, &items[next]), next) {
+                Ok(w) => workers.push(w),
+                Err(e) => {
+                    e.print(core);
+                    worst = worst.max(1);
+                },
+            }
+            next += 1;
+        }
+
+        if workers.is_empty() {
+            break;
+        }
+
+        collect_output(&mut workers);
+        for w in reap(core, &mut workers) {
+            worst = worst.max(w.status);
+            done.insert(w.index, w);
+        }
+
+        let limit = match keep_order {
+            true => workers.iter().map(|w| w.index).min().unwrap_or(next),
+            false => usize::MAX,
+        };
+        while let Some(entry) = done.first_entry() {
+            if *entry.key() >= limit {
+                break;
+            }
+            entry.remove().flush();
+        }
+    }
+
+    done.values().for_each(|w| w.flush());
+    core.tty_fd = backup;
+    worst
+}
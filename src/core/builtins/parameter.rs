@@ -2,7 +2,13 @@
 // SPDX-License-Identifier: BSD-3-Clause
 
 use super::error_exit;
-use crate::{ShellCore, elements::substitution::Substitution, env, error::exec::ExecError, utils::arg};
+use crate::{
+    ShellCore,
+    elements::substitution::Substitution,
+    env,
+    error::exec::ExecError,
+    utils::{arg, c_string},
+};
 
 pub fn local(core: &mut ShellCore, args: &mut Vec<String>, subs: &mut Vec<Substitution>) -> i32 {
     let layer = if core.db.get_layer_num() > 2 {
@@ -44,10 +50,19 @@ fn set_substitution(
 
     if arg::consume_option("+i", args) {
         if core.db.has_flag_layer(&sub.left_hand.name, 'i', layer) {
-            core.db.int_to_str_type(&sub.left_hand.name, layer)?;
+            core.db.drop_type_flag(&sub.left_hand.name, 'i', layer)?;
         }
     }
 
+    if arg::consume_option("+E", args) && core.db.has_flag_layer(&sub.left_hand.name, 'E', layer) {
+        core.db.drop_type_flag(&sub.left_hand.name, 'E', layer)?;
+    }
+
+    let float_opt = args.contains(&"-E".to_string()) && core.shopts.query("arith_float");
+    if !float_opt {
+        args.retain(|a| a != "-E");
+    }
+
     if (args.contains(&"-A".to_string()) || args.contains(&"-a".to_string())) && !core.db.exist(&sub.left_hand.name) {
         sub.left_hand.init_variable(core, Some(layer), args)?;
     }
@@ -72,6 +87,10 @@ fn set_substitution(
         core.db.set_flag(&sub.left_hand.name, 'l', Some(layer));
     }
 
+    if float_opt && !core.db.is_float(&sub.left_hand.name) {
+        core.db.set_flag(&sub.left_hand.name, 'E', Some(layer));
+    }
+
     let mut res = Ok(());
 
     match sub.has_right {
@@ -110,6 +129,10 @@ fn declare_print(core: &mut ShellCore, names: &[String], com: &str) -> i32 {
             opt += "i";
         }
 
+        if core.db.is_float(n) {
+            opt += "E";
+        }
+
         if core.db.has_flag(&n, 'l') {
             opt += "l";
         }
@@ -150,6 +173,13 @@ fn declare_print_all(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
         return 0;
     }
 
+    if args.len() == 2 && args[1] == "-F" {
+        let mut names: Vec<String> = core.db.functions.keys().map(|k| k.to_string()).collect();
+        names.sort();
+        names.iter().for_each(|n| println!("declare -f {}", n));
+        return 0;
+    }
+
     let mut names = core.db.get_keys();
     let mut options = String::new();
 
@@ -204,6 +234,17 @@ fn declare_print_function(core: &mut ShellCore, subs: &mut Vec<Substitution>) ->
     0
 }
 
+fn declare_print_function_name(core: &mut ShellCore, subs: &mut Vec<Substitution>) -> i32 {
+    let mut ans = 0;
+    for sub in subs {
+        match core.db.functions.contains_key(&sub.left_hand.name) {
+            true => println!("{}", sub.left_hand.name),
+            false => ans = 1,
+        }
+    }
+    ans
+}
+
 pub fn declare(core: &mut ShellCore, args: &mut Vec<String>, subs: &mut Vec<Substitution>) -> i32 {
     let mut args = arg::dissolve_options(args);
 
@@ -215,6 +256,10 @@ pub fn declare(core: &mut ShellCore, args: &mut Vec<String>, subs: &mut Vec<Subs
         return declare_print_function(core, subs);
     }
 
+    if arg::consume_option("-F", &mut args) {
+        return declare_print_function_name(core, subs);
+    }
+
     if arg::consume_option("-p", &mut args) {
         for sub in subs {
             args.push(sub.text.clone());
@@ -241,7 +286,7 @@ pub fn export(core: &mut ShellCore, args: &mut Vec<String>, subs: &mut Vec<Subst
             return 1;
         }
         match core.db.get_param(&sub.left_hand.name) {
-            Ok(v) => env::set_var(&sub.left_hand.name, v),
+            Ok(v) => env::set_var(&sub.left_hand.name, c_string::to_os_string(&v)),
             Err(e) => {
                 e.print(core);
                 return 1;
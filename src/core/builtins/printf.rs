@@ -1,388 +1,569 @@
 // SPDX-FileCopyrightText: 2024 Ryuichi Ueda <ryuichiueda@gmail.com>
 // SPDX-License-Identifier: BSD-3-Clause
 
-use std::io::{Write, stdout};
+use std::{
+    ffi::CString,
+    io::{Write, stdout},
+};
+
+use nix::{errno::Errno, libc};
 
+use super::error_exit;
 use crate::{
-    Feeder, ShellCore,
-    elements::substitution::Substitution,
-    error,
-    error::{arith::ArithError, exec::ExecError},
+    ShellCore,
+    elements::substitution::variable::Variable,
+    error::exec::ExecError,
+    utils::{clock, c_string, c_string::EscapeMode},
 };
 
+#[derive(Debug, Clone, Default)]
+struct Spec {
+    flags:     String,
+    width:     String,
+    precision: Option<String>,
+    conv:      char,
+}
+
 #[derive(Debug, Clone)]
 enum PrintfToken {
-    B(String),
-    DI(String),
-    F(String),
-    O(String),
-    S(String),
-    U(String),
-    X(String),
-    LargeX(String),
-    Q,
-    Other(String),
-    Normal(String),
-    EscapedChar(char),
+    Normal(Vec<u8>),
+    Conversion(Spec),
+    Time(Spec, String),
+    Invalid(String),
 }
 
-impl PrintfToken {
-    fn continue_(&self) -> bool {
-        match self {
-            Self::Normal(_) | Self::EscapedChar(_) => false,
-            _ => true,
+enum CArg {
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+}
+
+struct Printf<'a> {
+    core:   &'a mut ShellCore,
+    args:   Vec<String>,
+    pos:    usize,
+    out:    Vec<u8>,
+    to_var: bool,
+    status: i32,
+    stop:   bool,
+}
+
+impl Printf<'_> {
+    fn next_arg(&mut self) -> Option<String> {
+        let ans = self.args.get(self.pos).cloned();
+        if ans.is_some() {
+            self.pos += 1;
         }
+        ans
     }
 
-    fn to_int(s: &String) -> Result<isize, ExecError> {
-        match s.parse::<isize>() {
-            Ok(n) => Ok(n),
-            Err(_) => Err(ArithError::InvalidNumber(s.to_string()).into()),
+    fn flush(&mut self) {
+        if !self.to_var {
+            let _ = stdout().write_all(&self.out);
+            let _ = stdout().flush();
+            self.out.clear();
         }
     }
 
-    fn to_float(s: &String) -> Result<f64, ExecError> {
-        match s.parse::<f64>() {
-            Ok(n) => Ok(n),
-            Err(_) => Err(ArithError::InvalidNumber(s.to_string()).into()),
-        }
+    fn error(&mut self, msg: &str) {
+        self.flush();
+        self.status = error_exit(1, "printf", msg, self.core);
     }
 
-    // TODO: implement!
-    fn padding_float(_: &mut String, fmt: &mut String) {
-        if fmt.is_empty() {
-            return;
-        }
+    fn invalid_number(&mut self, arg: &str) {
+        let t = arg.trim_start();
+        let kind = if t.starts_with("0x") || t.starts_with("0X") {
+            "hex "
+        } else if t.len() > 1 && t.starts_with('0') && t[1..].starts_with(|c: char| c.is_ascii_digit()) {
+            "octal "
+        } else {
+            ""
+        };
+        self.error(&format!("{}: invalid {}number", arg, kind));
     }
 
-    fn padding(s: &mut String, fmt: &mut String, is_int: bool) {
-        if fmt.is_empty() {
-            return;
+    fn warning(&mut self, arg: &str) {
+        let msg = format!("warning: {}: Numerical result out of range", arg);
+        self.flush();
+        error_exit(0, "printf", &msg, self.core);
+    }
+
+    fn int_arg(&mut self) -> i64 {
+        let arg = match self.next_arg() {
+            Some(a) => a,
+            None => return 0,
+        };
+
+        let n = match parse_int(&arg) {
+            Ok(n) => n,
+            Err(n) => {
+                self.invalid_number(&arg);
+                n
+            },
+        };
+
+        if n > i64::MAX as i128 || n < i64::MIN as i128 {
+            self.warning(&arg);
         }
+        n.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+    }
 
-        let mut right = false;
-        let mut padding = ' ';
+    fn uint_arg(&mut self) -> u64 {
+        let arg = match self.next_arg() {
+            Some(a) => a,
+            None => return 0,
+        };
+
+        let n = match parse_int(&arg) {
+            Ok(n) => n,
+            Err(n) => {
+                self.invalid_number(&arg);
+                n
+            },
+        };
 
-        if fmt.starts_with("-") {
-            fmt.remove(0);
-            right = true;
+        if n < 0 && n >= i64::MIN as i128 {
+            return n as i64 as u64;
         }
-        if fmt.starts_with("0") {
-            padding = fmt.remove(0);
+        if n > u64::MAX as i128 || n < 0 {
+            self.warning(&arg);
         }
+        n.clamp(0, u64::MAX as i128) as u64
+    }
 
-        if !is_int {
-            padding = ' ';
+    fn float_arg(&mut self) -> f64 {
+        let arg = match self.next_arg() {
+            Some(a) => a,
+            None => return 0.0,
+        };
+
+        match parse_float(&arg) {
+            Ok(f) => f,
+            Err(f) => {
+                self.error(&format!("{}: invalid number", &arg));
+                f
+            },
         }
+    }
 
-        let len = fmt.parse::<usize>().unwrap_or(0);
-        if right {
-            while s.len() < len {
-                s.push(' ');
-            }
-            return;
+    fn time_arg(&mut self) -> i64 {
+        let given = self.pos < self.args.len();
+        match self.int_arg() {
+            -1 => clock::get_epochseconds().parse().unwrap_or(0),
+            -2 => self.core.start_time,
+            _ if !given => clock::get_epochseconds().parse().unwrap_or(0),
+            n => n,
         }
+    }
 
-        if is_int && s.starts_with("-") && padding == '0' {
-            while s.len() < len {
-                s.insert(1, '0');
-            }
-            return;
+    // returns (left justified, width, precision)
+    fn width_and_precision(&mut self, spec: &Spec) -> (bool, usize, Option<usize>) {
+        let mut left = spec.flags.contains('-');
+        let width = match spec.width.as_str() {
+            "*" => {
+                let w = self.int_arg();
+                left |= w < 0;
+                w.unsigned_abs() as usize
+            },
+            w => w.parse::<usize>().unwrap_or(0),
+        };
+
+        let precision = match spec.precision.as_deref() {
+            Some("*") => usize::try_from(self.int_arg()).ok(),
+            Some(p) => Some(p.parse::<usize>().unwrap_or(0)),
+            None => None,
+        };
+
+        (left, width, precision)
+    }
+
+    fn write_str(&mut self, mut s: Vec<u8>, left: bool, width: usize, precision: Option<usize>) {
+        if let Some(p) = precision {
+            s.truncate(p);
         }
 
-        while s.len() < len {
-            s.insert(0, padding);
+        let padding = vec![b' '; width.saturating_sub(s.len())];
+        match left {
+            true => self.out.extend(s.iter().chain(padding.iter())),
+            false => self.out.extend(padding.iter().chain(s.iter())),
         }
     }
 
-    fn to_string(&mut self, args: &mut Vec<String>) -> Result<String, ExecError> {
-        match self {
-            Self::DI(fmt) => {
-                let mut a = pop(args);
-                Self::padding(&mut a, &mut fmt.clone(), true);
-                Ok(a)
-            },
-            Self::U(fmt) => {
-                let mut a = pop(args);
-                if a.starts_with("-") {
-                    a.remove(0);
-                    let mut num = a.parse::<u64>()?;
-                    num = std::u64::MAX - num + 1;
-                    let mut a = num.to_string();
-                    Self::padding(&mut a, &mut fmt.clone(), true);
-                    Ok(a)
-                } else {
-                    Self::padding(&mut a, &mut fmt.clone(), true);
-                    Ok(a)
-                }
-            },
-            Self::F(fmt) => {
-                let mut a = format!("{:.6}", Self::to_float(&pop(args))?);
-                Self::padding_float(&mut a, &mut fmt.clone());
-                Ok(a)
-            },
-            Self::S(fmt) => {
-                let mut a = pop(args);
-                Self::padding(&mut a, &mut fmt.clone(), false);
-                Ok(a)
-            },
-            Self::B(fmt) => {
-                let mut a = replace_escape(&pop(args));
-                Self::padding(&mut a, &mut fmt.clone(), false);
-                Ok(a)
+    fn write_num(&mut self, spec: &Spec) {
+        let (left, width, precision) = self.width_and_precision(spec);
+
+        let mut fmt = "%".to_string() + &spec.flags;
+        if left && !fmt.contains('-') {
+            fmt.push('-');
+        }
+        if width > 0 {
+            fmt += &width.to_string();
+        }
+        if let Some(p) = precision {
+            fmt += &format!(".{}", p);
+        }
+
+        let arg = match spec.conv {
+            'd' | 'i' => {
+                fmt += "ll";
+                CArg::Int(self.int_arg())
             },
-            Self::X(fmt) => {
-                let mut a = format!("{:x}", Self::to_int(&pop(args))?);
-                Self::padding(&mut a, &mut fmt.clone(), true);
-                Ok(a)
+            'o' | 'u' | 'x' | 'X' => {
+                fmt += "ll";
+                CArg::UInt(self.uint_arg())
             },
-            Self::O(fmt) => {
-                let mut a = format!("{:o}", Self::to_int(&pop(args))?);
-                Self::padding(&mut a, &mut fmt.clone(), true);
-                Ok(a)
+            _ => CArg::Float(self.float_arg()),
+        };
+        fmt.push(spec.conv);
+
+        let s = c_format(&fmt, arg);
+        self.out.extend(s);
+    }
+
+    fn write_conversion(&mut self, spec: &Spec) {
+        if !"sbqQc".contains(spec.conv) {
+            return self.write_num(spec);
+        }
+
+        let (left, width, precision) = self.width_and_precision(spec);
+        let arg = self.next_arg();
+        let s = match spec.conv {
+            'b' => {
+                let (s, stop) = c_string::unescape(c_string::to_carg(&arg.unwrap_or_default()).as_bytes(), EscapeMode::Arg);
+                self.stop |= stop;
+                s
             },
-            Self::LargeX(fmt) => {
-                let mut a = format!("{:X}", Self::to_int(&pop(args))?);
-                Self::padding(&mut a, &mut fmt.clone(), true);
-                Ok(a)
+            'q' => quote(&arg.unwrap_or_default()).into_bytes(),
+            'Q' => {
+                let a = arg.unwrap_or_default();
+                let a: String = match precision {
+                    Some(p) => a.chars().take(p).collect(),
+                    None => a,
+                };
+                return self.write_str(quote(&a).into_bytes(), left, width, None);
             },
-            Self::Q => {
-                let a = pop(args);
-                let q = a
-                    .replace("\\", "\\\\")
-                    .replace("$", "\\$")
-                    .replace("|", "\\|")
-                    .replace("\"", "\\\"")
-                    .replace("'", "\\\'")
-                    .replace("~", "\\~")
-                    .replace("(", "\\(")
-                    .replace(")", "\\)")
-                    .replace("{", "\\{")
-                    .replace("}", "\\}")
-                    .replace("!", "\\!")
-                    .replace("&", "\\&");
-                Ok(q)
+            'c' => match arg.and_then(|a| a.chars().next()) {
+                Some(c) => c.to_string().into_bytes(),
+                None => vec![0],
             },
-            Self::Other(s) => {
-                let a = pop(args);
-                let formatted = match sprintf::sprintf!(&s, a) {
-                    Ok(res) => res,
-                    Err(e) => {
-                        let msg = format!("{} {} {}", &e, &s, &a);
-                        return Err(ExecError::Other(msg));
+            _ => c_string::to_carg(&arg.unwrap_or_default()).into_bytes(),
+        };
+        self.write_str(s, left, width, precision);
+    }
+
+    fn write_time(&mut self, spec: &Spec, fmt: &str) {
+        let (left, width, precision) = self.width_and_precision(spec);
+        let t = self.time_arg();
+        self.write_str(strftime(fmt, t), left, width, precision);
+    }
+
+    fn format(&mut self, tokens: &[PrintfToken]) {
+        loop {
+            let start = self.pos;
+            for tok in tokens {
+                match tok {
+                    PrintfToken::Normal(s) => self.out.extend(s),
+                    PrintfToken::Conversion(spec) => self.write_conversion(spec),
+                    PrintfToken::Time(spec, fmt) => self.write_time(spec, fmt),
+                    PrintfToken::Invalid(msg) => {
+                        self.error(msg);
+                        self.stop = true;
                     },
-                };
+                }
 
-                Ok(formatted)
-            },
-            Self::EscapedChar(c) => Ok(esc_to_str(*c)),
-            Self::Normal(s) => Ok(s.clone()),
+                if self.stop {
+                    return;
+                }
+            }
+
+            if self.pos == start || self.pos >= self.args.len() {
+                return;
+            }
         }
     }
 }
 
-fn pop(args: &mut Vec<String>) -> String {
-    match args.is_empty() {
-        true => "".to_string(),
-        false => args.remove(0),
+// Err holds the value of the valid prefix
+fn parse_int(s: &str) -> Result<i128, i128> {
+    let s = s.trim_start();
+    if s.is_empty() {
+        return Ok(0);
+    }
+    if let Some(rest) = s.strip_prefix(['\'', '"']) {
+        return Ok(rest.chars().next().map(|c| c as i128).unwrap_or(0));
+    }
+
+    let (neg, s) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+
+    let (radix, digits) = if let Some(rest) = s.strip_prefix("0x").or(s.strip_prefix("0X")) {
+        (16, rest)
+    } else if s.len() > 1 && s.starts_with('0') {
+        (8, &s[1..])
+    } else {
+        (10, s)
+    };
+
+    let mut n: i128 = 0;
+    let mut len = 0;
+    for c in digits.chars() {
+        match c.to_digit(radix) {
+            Some(d) => n = n.saturating_mul(radix as i128).saturating_add(d as i128),
+            None => break,
+        }
+        len += 1;
+    }
+
+    let n = if neg { -n } else { n };
+    match (len == 0 && radix != 8) || len < digits.len() {
+        true => Err(n),
+        false => Ok(n),
     }
 }
 
-fn esc_to_str(ch: char) -> String {
-    match ch {
-        'a' => char::from(7).to_string(),
-        'b' => char::from(8).to_string(),
-        'e' | 'E' => char::from(27).to_string(),
-        'f' => char::from(12).to_string(),
-        'n' => "\n".to_string(),
-        'r' => "\r".to_string(),
-        't' => "\t".to_string(),
-        'v' => char::from(11).to_string(),
-        '\\' => "\\".to_string(),
-        '\'' => "'".to_string(),
-        '"' => "\"".to_string(),
-        _ => ("\\".to_owned() + &ch.to_string()).to_string(),
+fn parse_float(s: &str) -> Result<f64, f64> {
+    let t = s.trim_start();
+    if t.is_empty() || t.starts_with(['\'', '"']) {
+        return parse_int(t).map(|n| n as f64).map_err(|n| n as f64);
+    }
+    if let Ok(f) = t.parse::<f64>() {
+        return Ok(f);
+    }
+    if let Ok(n) = parse_int(t) {
+        return Ok(n as f64);
+    }
+
+    for end in (1..t.len()).rev().filter(|e| t.is_char_boundary(*e)) {
+        if let Ok(f) = t[..end].parse::<f64>() {
+            return Err(f);
+        }
     }
+    Err(0.0)
 }
 
-fn replace_escape(s: &str) -> String {
-    let mut ans = String::new();
-    let mut esc = false;
+fn c_format(fmt: &str, arg: CArg) -> Vec<u8> {
+    let cfmt = match CString::new(fmt) {
+        Ok(f) => f,
+        Err(_) => return vec![],
+    };
 
-    for ch in s.chars() {
-        if esc || ch == '\\' {
-            if esc {
-                ans.push_str(&esc_to_str(ch));
+    let mut buf = vec![0u8; 64];
+    loop {
+        let ptr = buf.as_mut_ptr() as *mut libc::c_char;
+        let len = unsafe {
+            match arg {
+                CArg::Int(n) => libc::snprintf(ptr, buf.len(), cfmt.as_ptr(), n as libc::c_longlong),
+                CArg::UInt(n) => libc::snprintf(ptr, buf.len(), cfmt.as_ptr(), n as libc::c_ulonglong),
+                CArg::Float(f) => libc::snprintf(ptr, buf.len(), cfmt.as_ptr(), f as libc::c_double),
             }
-            esc = !esc;
-            continue;
+        };
+
+        let len = match usize::try_from(len) {
+            Ok(n) => n,
+            Err(_) => return vec![],
+        };
+        if len < buf.len() {
+            buf.truncate(len);
+            return buf;
         }
-
-        ans.push(ch);
+        buf.resize(len + 1, 0);
     }
+}
 
-    ans
+extern "C" {
+    fn tzset();
 }
 
-fn scanner_normal(remaining: &str) -> usize {
-    let mut pos = 0;
+fn strftime(fmt: &str, t: i64) -> Vec<u8> {
+    let fmt = if fmt.is_empty() { "%X" } else { fmt };
+    let cfmt = match CString::new(fmt) {
+        Ok(f) => f,
+        Err(_) => return vec![],
+    };
 
-    for c in remaining.chars() {
-        if c == '%' || c == '\\' {
-            break;
+    let t = t as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    unsafe {
+        tzset();
+        libc::localtime_r(&t, &mut tm);
+    }
+
+    let mut buf = vec![0u8; 256];
+    while buf.len() <= 65536 {
+        let ptr = buf.as_mut_ptr() as *mut libc::c_char;
+        let len = unsafe { libc::strftime(ptr, buf.len(), cfmt.as_ptr(), &tm) };
+        if len > 0 {
+            buf.truncate(len);
+            return buf;
         }
-        pos += c.len_utf8();
+        buf.resize(buf.len() * 2, 0);
     }
-    pos
+    vec![]
 }
 
-fn scanner_escaped_char(remaining: &str) -> usize {
-    if !remaining.starts_with("\\") {
-        return 0;
+fn quote(s: &str) -> String {
+    if s.is_empty() {
+        return "''".to_string();
     }
-
-    match remaining.chars().nth(1) {
-        Some(ch) => 1 + ch.len_utf8(),
-        _ => 0,
+    if s.chars().any(|c| c.is_control()) {
+        return ansi_c_quote(s);
     }
-}
 
-fn scanner_format_num(remaining: &str) -> usize {
-    let mut ans = 0;
-    for c in remaining.chars() {
-        if !"-.".contains(c) && (c < '0' || c > '9') {
-            break;
+    let mut ans = String::new();
+    for (i, c) in s.chars().enumerate() {
+        if " '\"\\|&;()<>!{}*[?]^$`,".contains(c) || (i == 0 && "~#".contains(c)) {
+            ans.push('\\');
         }
-
-        ans += 1;
+        ans.push(c);
     }
     ans
 }
 
-fn parse(pattern: &str) -> Vec<PrintfToken> {
-    let mut remaining = pattern.to_string();
-    let mut ans = vec![];
-
-    while !remaining.is_empty() {
-        let len = scanner_normal(&remaining);
-        if len > 0 {
-            let tail = remaining.split_off(len);
-            ans.push(PrintfToken::Normal(remaining));
-            remaining = tail;
-            continue;
+fn ansi_c_quote(s: &str) -> String {
+    let mut ans = "$'".to_string();
+    for c in s.chars() {
+        match c {
+            '\x07' => ans += "\\a",
+            '\x08' => ans += "\\b",
+            '\x1b' => ans += "\\E",
+            '\x0c' => ans += "\\f",
+            '\n' => ans += "\\n",
+            '\r' => ans += "\\r",
+            '\t' => ans += "\\t",
+            '\x0b' => ans += "\\v",
+            '\\' => ans += "\\\\",
+            '\'' => ans += "\\'",
+            c if c.is_control() => {
+                for b in c.to_string().bytes() {
+                    ans += &format!("\\{:03o}", b);
+                }
+            },
+            c => ans.push(c),
         }
+    }
+    ans + "'"
+}
 
-        let len = scanner_escaped_char(&remaining);
-        if len > 0 {
-            remaining.remove(0);
-            ans.push(PrintfToken::EscapedChar(remaining.remove(0)));
-            continue;
+fn parse_spec(chars: &[char], pos: &mut usize) -> PrintfToken {
+    let mut spec = Spec::default();
+    let eat = |pos: &mut usize, cond: &dyn Fn(char) -> bool| {
+        let mut ans = String::new();
+        while let Some(c) = chars.get(*pos).filter(|c| cond(**c)) {
+            ans.push(*c);
+            *pos += 1;
         }
+        ans
+    };
 
-        if remaining.starts_with("%") {
-            remaining.remove(0); // %
+    spec.flags = eat(pos, &|c| "-+ #0'".contains(c));
+    spec.width = match chars.get(*pos) {
+        Some('*') => {
+            *pos += 1;
+            "*".to_string()
+        },
+        _ => eat(pos, &|c| c.is_ascii_digit()),
+    };
+    if chars.get(*pos) == Some(&'.') {
+        *pos += 1;
+        spec.precision = match chars.get(*pos) {
+            Some('*') => {
+                *pos += 1;
+                Some("*".to_string())
+            },
+            _ => Some(eat(pos, &|c| c.is_ascii_digit())),
+        };
+    }
+    eat(pos, &|c| "hlLjtz".contains(c));
 
-            let mut num_part = String::new();
-            let len = scanner_format_num(&remaining);
-            if len > 0 {
-                let tail = remaining.split_off(len);
-                num_part = remaining.clone();
-                remaining = tail;
-            }
+    let c = match chars.get(*pos) {
+        Some(c) => *c,
+        None => return PrintfToken::Invalid("`%': missing format character".to_string()),
+    };
+    *pos += 1;
 
-            let token = match remaining.chars().next() {
-                Some('b') => PrintfToken::B(num_part),
-                Some('d') => PrintfToken::DI(num_part),
-                Some('i') => PrintfToken::DI(num_part),
-                Some('f') => PrintfToken::F(num_part),
-                Some('o') => PrintfToken::O(num_part),
-                Some('s') => PrintfToken::S(num_part),
-                Some('u') => PrintfToken::U(num_part),
-                Some('x') => PrintfToken::X(num_part),
-                Some('X') => PrintfToken::LargeX(num_part),
-                Some('q') => PrintfToken::Q,
-                Some(c) => PrintfToken::Other("%".to_owned() + &num_part + &c.to_string()),
-                None => PrintfToken::Normal("%".to_string()),
-            };
-
-            remaining.remove(0);
-            ans.push(token);
+    if c == '(' {
+        let fmt = eat(pos, &|c| c != ')');
+        if chars.get(*pos) == Some(&')') && chars.get(*pos + 1) == Some(&'T') {
+            *pos += 2;
+            return PrintfToken::Time(spec, fmt);
         }
+        return PrintfToken::Invalid("`(': invalid format character".to_string());
     }
 
-    ans
+    match "diouxXfFeEgGaAcsbqQ".contains(c) {
+        true => {
+            spec.conv = c;
+            PrintfToken::Conversion(spec)
+        },
+        false => PrintfToken::Invalid(format!("`{}': invalid format character", c)),
+    }
 }
 
-fn format(pattern: &str, args: &mut Vec<String>) -> Result<String, ExecError> {
-    let mut ans = String::new();
+fn parse(pattern: &str) -> Vec<PrintfToken> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut ans = vec![];
+    let mut pos = 0;
+    let mut normal = String::new();
 
-    let mut tokens = parse(pattern);
-    let mut fin = true;
+    while pos < chars.len() {
+        if chars[pos] != '%' {
+            normal.push(chars[pos]);
+            pos += 1;
+            continue;
+        }
 
-    for tok in tokens.iter_mut() {
-        if tok.continue_() {
-            fin = false;
+        if !normal.is_empty() {
+            ans.push(PrintfToken::Normal(c_string::unescape(c_string::to_carg(&normal).as_bytes(), EscapeMode::Format).0));
+            normal.clear();
         }
-        ans += &tok.to_string(args)?;
-    }
 
-    if !args.is_empty() && !fin {
-        if let Ok(s) = format(pattern, args) {
-            ans += &s;
+        pos += 1;
+        if chars.get(pos) == Some(&'%') {
+            ans.push(PrintfToken::Normal(b"%".to_vec()));
+            pos += 1;
+            continue;
+        }
+
+        let tok = parse_spec(&chars, &mut pos);
+        let invalid = matches!(tok, PrintfToken::Invalid(_));
+        ans.push(tok);
+        if invalid {
+            return ans;
         }
     }
-    Ok(ans)
+
+    if !normal.is_empty() {
+        ans.push(PrintfToken::Normal(c_string::unescape(c_string::to_carg(&normal).as_bytes(), EscapeMode::Format).0));
+    }
+    ans
 }
 
+const USAGE: &str = "printf: usage: printf [-v var] format [arguments]";
+
 fn arg_check(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
     if args.len() < 2 || args[1] == "--help" || args[1] == "-v" && args.len() == 3 {
-        let msg = format!("printf: usage: printf [-v var] format [arguments]");
-        error::print(&msg, core);
+        eprintln!("{}", USAGE);
         return 2;
     }
 
     if args[1] == "-v" && args.len() == 2 {
-        let msg = format!("printf: -v: option requires an argument");
-        error::print(&msg, core);
-        let msg = format!("printf: usage: printf [-v var] format [arguments]");
-        error::print(&msg, core);
+        error_exit(2, "printf", "-v: option requires an argument", core);
+        eprintln!("{}", USAGE);
         return 2;
     }
 
     0
 }
 
-fn printf_v(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
-    if args[3] == "--" {
-        args.remove(3);
-    }
-
-    let s = match format(&args[3], &mut args[4..].to_vec()) {
-        Ok(ans) => ans,
-        Err(e) => {
-            let msg = String::from(&e);
-            return super::error_exit(1, "printf", &msg, core);
-        },
-    };
-
-    if args[2].contains("[") {
-        let mut f = Feeder::new(&(args[2].clone() + "=" + &s));
-        if let Ok(Some(mut a)) = Substitution::parse(&mut f, core, false) {
-            if let Err(e) = a.eval(core, None, false) {
-                let msg = String::from(&e);
-                return super::error_exit(2, "printf", &msg, core);
-            }
-        } else {
-            return 1;
+fn set_var(var: &str, value: &str, core: &mut ShellCore) -> Result<(), ExecError> {
+    if let Some((name, key)) = var.strip_suffix(']').and_then(|v| v.split_once('[')) {
+        if core.db.is_assoc(name) {
+            return core.db.set_assoc_elem(name, &key.to_string(), &value.to_string(), None);
         }
-        return 0;
-    }
-    if let Err(e) = core.db.set_param(&args[2], &s, None) {
-        let msg = String::from(&e);
-        return super::error_exit(2, "printf", &msg, core);
     }
-
-    return 0;
+    Variable::parse_and_set(var, value, core)
 }
 
 pub fn printf(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
@@ -391,19 +572,45 @@ pub fn printf(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
         n => return n,
     }
 
+    let mut var = None;
     if args[1] == "-v" {
-        return printf_v(core, args);
+        var = Some(args.remove(2));
+        args.remove(1);
+    }
+    if args.len() > 1 && args[1] == "--" {
+        args.remove(1);
+    }
+    if args.len() < 2 {
+        eprintln!("{}", USAGE);
+        return 2;
     }
 
-    let s = match format(&args[1], &mut args[2..].to_vec()) {
-        Ok(ans) => ans,
-        Err(e) => {
-            let msg = format!("printf: {:?}", e);
-            error::print(&msg, core);
-            return 1;
-        },
+    let tokens = parse(&args[1]);
+    let mut p = Printf {
+        core,
+        args: args[2..].to_vec(),
+        pos: 0,
+        out: vec![],
+        to_var: var.is_some(),
+        status: 0,
+        stop: false,
     };
-    print!("{}", &s);
-    stdout().flush().unwrap();
-    0
+    p.format(&tokens);
+    let (out, status) = (p.out, p.status);
+
+    if let Some(var) = var {
+        let end = out.iter().position(|b| *b == 0).unwrap_or(out.len());
+        let s = String::from_utf8_lossy(&out[..end]).to_string();
+        if let Err(e) = set_var(&var, &s, core) {
+            return error_exit(2, "printf", &String::from(&e), core);
+        }
+        return status;
+    }
+
+    let mut stdout = stdout();
+    if let Err(e) = stdout.write_all(&out).and_then(|_| stdout.flush()) {
+        let errno = Errno::from_raw(e.raw_os_error().unwrap_or(0));
+        return error_exit(1, "printf", &format!("write error: {}", errno.desc()), core);
+    }
+    status
 }
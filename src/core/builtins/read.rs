@@ -1,35 +1,34 @@
 // SPDX-FileCopyrightText: 2024 Ryuichi Ueda <ryuichiueda@gmail.com>
 // SPDX-License-Identifier: BSD-3-Clause
 
-use super::error_exit;
-use crate::{ShellCore, arg, elements::substitution::variable::Variable, error, utils};
-
-fn check_word_limit(word: &mut String, limit: &mut usize) -> bool {
-    let mut pos = 0;
-    for c in word.chars() {
-        if *limit == 0 {
-            let _ = word.split_off(pos);
-            return true;
-        }
-        *limit -= 1;
-
-        pos += c.len_utf8();
-    }
-    false
-}
-
-pub fn read_(
-    core: &mut ShellCore,
-    args: &mut Vec<String>,
-    ignore_escape: bool,
-    limit: &mut usize,
-    delim: &String,
-) -> i32 {
-    let mut remaining = utils::read_line_stdin_unbuffered(delim).unwrap_or("".to_string());
-    if remaining.is_empty() {
-        return 1;
-    }
+use std::{
+    os::fd::{BorrowedFd, RawFd},
+    sync::atomic::Ordering::Relaxed,
+    time::{Duration, Instant},
+};
+
+use nix::{
+    errno::Errno,
+    poll,
+    poll::{PollFd, PollFlags, PollTimeout},
+    sys::{
+        signal::Signal,
+        termios,
+        termios::{LocalFlags, SetArg, SpecialCharacterIndices},
+    },
+    unistd,
+};
 
+use super::error_exit;
+use crate::{
+    ShellCore,
+    elements::{io, substitution::variable::Variable},
+    error,
+    error::input::InputError,
+    feeder::terminal,
+};
+
+pub fn read_(core: &mut ShellCore, mut args: Vec<String>, mut remaining: String, ignore_escape: bool, delim: &str) -> i32 {
     let ifs = match core.db.exist("IFS") {
         true => core.db.get_param("IFS").unwrap(),
         false => " \t\n".to_string(),
@@ -38,24 +37,14 @@ pub fn read_(
     let mut tail_space = ifs.chars().filter(|i| " \t\n".contains(*i)).collect::<String>();
     tail_space += delim;
 
-    args.remove(0);
-    if args.len() == 0 {
-        args.push("REPLY".to_string());
-    }
-
-    consume_ifs(&mut remaining, " \t", limit);
-
-    while args.len() > 0 && !remaining.is_empty() && *limit != 0 {
-        let mut word = match eat_word(core, &mut remaining, &ifs, ignore_escape, delim) {
-            Some(w) => w,
-            None => break,
-        };
+    consume_ifs(&mut remaining, " \t");
 
-        check_word_limit(&mut word, limit);
+    while args.len() > 0 {
+        let mut word = eat_word(&mut remaining, &ifs, ignore_escape);
 
-        if args.len() == 1 && *limit != 0 {
+        if args.len() == 1 {
             let bkup = remaining.clone();
-            consume_ifs(&mut remaining, &ifs, limit);
+            consume_ifs(&mut remaining, &ifs);
 
             if remaining.is_empty() || remaining == "\n" {
             } else {
@@ -70,18 +59,13 @@ pub fn read_(
         }
 
         args.remove(0);
-        consume_ifs(&mut remaining, &ifs, limit);
+        consume_ifs(&mut remaining, &ifs);
     }
 
     0
 }
 
-pub fn read_a(core: &mut ShellCore, name: &String, ignore_escape: bool, limit: &mut usize, delim: &String) -> i32 {
-    let mut remaining = utils::read_line_stdin_unbuffered(delim).unwrap_or("".to_string());
-    if remaining.is_empty() {
-        return 1;
-    }
-
+pub fn read_a(core: &mut ShellCore, name: &String, mut remaining: String, ignore_escape: bool, delim: &str) -> i32 {
     let ifs = match core.db.exist("IFS") {
         true => core.db.get_param("IFS").unwrap(),
         false => " \t\n".to_string(),
@@ -90,15 +74,15 @@ pub fn read_a(core: &mut ShellCore, name: &String, ignore_escape: bool, limit: &
     let mut tail_space = ifs.chars().filter(|i| " \t\n".contains(*i)).collect::<String>();
     tail_space += delim;
 
-    consume_ifs(&mut remaining, " \t", limit);
+    consume_ifs(&mut remaining, " \t");
+
+    if let Err(e) = core.db.set_array(name, Some(vec![]), None) {
+        return super::error_exit(1, "read", &String::from(&e), core);
+    }
 
     let mut pos = 0;
-    while !remaining.is_empty() {
-        let mut word = match eat_word(core, &mut remaining, &ifs, ignore_escape, delim) {
-            Some(w) => w,
-            None => break,
-        };
-        check_word_limit(&mut word, limit);
+    while !remaining.is_empty() && remaining != delim {
+        let mut word = eat_word(&mut remaining, &ifs, ignore_escape);
         consume_tail_ifs(&mut word, &tail_space);
 
         if let Err(e) = core.db.set_array_elem(name, &word, pos, None) {
@@ -107,51 +91,341 @@ pub fn read_a(core: &mut ShellCore, name: &String, ignore_escape: bool, limit: &
             return 1;
         }
         pos += 1;
-        consume_ifs(&mut remaining, &ifs, limit);
+        consume_ifs(&mut remaining, &ifs);
+    }
+
+    0
+}
+
+fn unescape(line: &str) -> String {
+    let mut ans = String::new();
+    let mut esc = false;
+    for c in line.chars() {
+        if c == '\\' && !esc {
+            esc = true;
+            continue;
+        }
+        esc = false;
+        ans.push(c);
+    }
+    ans
+}
+
+fn set_reply(core: &mut ShellCore, names: &[String], line: &str, opts: &ReadOptions) -> i32 {
+    let mut value = match opts.raw {
+        true => line.to_string(),
+        false => unescape(line),
+    };
+    if !opts.exact && value.ends_with(opts.delim as char) {
+        value.pop();
     }
 
+    let mut names = names.to_vec();
+    if names.is_empty() {
+        names.push("REPLY".to_string());
+    }
+    for (i, name) in names.iter().enumerate() {
+        let v = if i == 0 { &value } else { "" };
+        if let Err(e) = Variable::parse_and_set(name, v, core) {
+            return super::error_exit(1, "read", &String::from(&e), core);
+        }
+    }
     0
 }
 
+const USAGE: &str = "read: usage: read [-ers] [-a array] [-d delim] [-i text] [-n nchars] [-N nchars] [-p prompt] [-t timeout] [-u fd] [name ...]";
+
+#[derive(Debug)]
+struct ReadOptions {
+    raw:     bool,
+    silent:  bool,
+    editor:  bool,
+    exact:   bool,
+    array:   Option<String>,
+    delim:   u8,
+    nchars:  Option<usize>,
+    prompt:  Option<String>,
+    text:    String,
+    timeout: Option<f64>,
+    fd:      RawFd,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        ReadOptions {
+            raw:     false,
+            silent:  false,
+            editor:  false,
+            exact:   false,
+            array:   None,
+            delim:   b'\n',
+            nchars:  None,
+            prompt:  None,
+            text:    String::new(),
+            timeout: None,
+            fd:      0,
+        }
+    }
+}
+
+impl ReadOptions {
+    fn set(&mut self, opt: char, value: &str, core: &mut ShellCore) -> Result<(), i32> {
+        match opt {
+            'a' => self.array = Some(value.to_string()),
+            'd' => self.delim = value.bytes().next().unwrap_or(0),
+            'i' => self.text = value.to_string(),
+            'p' => self.prompt = Some(value.to_string()),
+            'n' | 'N' => match value.parse::<usize>() {
+                Ok(n) => {
+                    self.nchars = Some(n);
+                    self.exact = opt == 'N';
+                },
+                Err(_) => return Err(error_exit(1, "read", &format!("{}: invalid number", value), core)),
+            },
+            't' => match value.parse::<f64>() {
+                Ok(t) if t >= 0.0 && t.is_finite() => self.timeout = Some(t),
+                _ => return Err(error_exit(1, "read", &format!("{}: invalid timeout specification", value), core)),
+            },
+            'u' => match value.parse::<RawFd>() {
+                Ok(fd) if io::is_open(fd) => self.fd = fd,
+                _ => {
+                    let msg = format!("{}: invalid file descriptor: Bad file descriptor", value);
+                    return Err(error_exit(1, "read", &msg, core));
+                },
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+
+    fn parse(core: &mut ShellCore, args: &[String]) -> Result<(Self, Vec<String>), i32> {
+        let mut opts = Self::default();
+        let mut i = 1;
+        while i < args.len() {
+            if args[i] == "--" {
+                i += 1;
+                break;
+            }
+            if !args[i].starts_with("-") || args[i] == "-" {
+                break;
+            }
+
+            let chars: Vec<char> = args[i][1..].chars().collect();
+            for (j, c) in chars.iter().enumerate() {
+                match c {
+                    'r' => opts.raw = true,
+                    's' => opts.silent = true,
+                    'e' => opts.editor = true,
+                    'a' | 'd' | 'i' | 'n' | 'N' | 'p' | 't' | 'u' => {
+                        let mut value: String = chars[j + 1..].iter().collect();
+                        if value.is_empty() {
+                            i += 1;
+                            value = match args.get(i) {
+                                Some(v) => v.clone(),
+                                None => {
+                                    error_exit(2, "read", &format!("-{}: option requires an argument", c), core);
+                                    eprintln!("{}", USAGE);
+                                    return Err(2);
+                                },
+                            };
+                        }
+                        opts.set(*c, &value, core)?;
+                        break;
+                    },
+                    _ => {
+                        error_exit(2, "read", &format!("-{}: invalid option", c), core);
+                        eprintln!("{}", USAGE);
+                        return Err(2);
+                    },
+                }
+            }
+            i += 1;
+        }
+
+        Ok((opts, args[i..].to_vec()))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum ReadEnd {
+    Delim,
+    Limit,
+    Eof,
+    Timeout,
+    Interrupted,
+}
+
+fn wait_input(core: &ShellCore, fd: RawFd, deadline: &Option<Instant>) -> bool {
+    let deadline = match deadline {
+        Some(d) => d,
+        None => return true,
+    };
+
+    let fd = unsafe { BorrowedFd::borrow_raw(fd) };
+    while !core.sigint.load(Relaxed) {
+        let rest = deadline.saturating_duration_since(Instant::now());
+        let mut fds = [PollFd::new(fd, PollFlags::POLLIN)];
+        let timeout = PollTimeout::try_from(rest.min(Duration::from_millis(100))).unwrap_or(PollTimeout::MAX);
+        match poll::poll(&mut fds, timeout) {
+            Ok(n) if n > 0 => return true,
+            Ok(_) if rest.is_zero() => return false,
+            Ok(_) | Err(Errno::EINTR) => continue,
+            Err(_) => return false,
+        }
+    }
+    false
+}
+
+fn utf8_following_bytes(b: u8) -> usize {
+    match b {
+        0xF0..=0xFF => 3,
+        0xE0..=0xEF => 2,
+        0xC0..=0xDF => 1,
+        _ => 0,
+    }
+}
+
+fn read_bytes(core: &mut ShellCore, opts: &ReadOptions) -> (Vec<u8>, ReadEnd) {
+    let deadline = opts.timeout.map(|t| Instant::now() + Duration::from_secs_f64(t));
+    let mut ans = vec![];
+    let mut chars = 0;
+    let mut following = 0;
+    let mut escaped = false;
+
+    loop {
+        if following == 0 && opts.nchars.is_some_and(|n| chars >= n) {
+            return (ans, ReadEnd::Limit);
+        }
+        if !wait_input(core, opts.fd, &deadline) {
+            return match core.sigint.load(Relaxed) {
+                true => (ans, ReadEnd::Interrupted),
+                false => (ans, ReadEnd::Timeout),
+            };
+        }
+
+        let mut buf = [0u8; 1];
+        match unistd::read(opts.fd, &mut buf) {
+            Ok(1) => {},
+            Err(Errno::EINTR) if !core.sigint.load(Relaxed) => continue,
+            Err(Errno::EINTR) => return (ans, ReadEnd::Interrupted),
+            _ => return (ans, ReadEnd::Eof),
+        }
+        let b = buf[0];
+        ans.push(b);
+
+        if following > 0 {
+            following -= 1;
+            continue;
+        }
+        following = utf8_following_bytes(b);
+
+        if escaped {
+            escaped = false;
+            if b == b'\n' {
+                ans.truncate(ans.len() - 2); // line continuation
+                continue;
+            }
+        } else if b == b'\\' && !opts.raw {
+            escaped = true;
+            continue;
+        } else if b == opts.delim && !opts.exact {
+            return (ans, ReadEnd::Delim);
+        }
+        chars += 1;
+    }
+}
+
+fn read_with_editor(core: &mut ShellCore, opts: &ReadOptions) -> (Vec<u8>, ReadEnd) {
+    let prompt = opts.prompt.clone().unwrap_or_default();
+    match terminal::read_line_with_text(core, &prompt, &opts.text) {
+        Ok(line) => (line.into_bytes(), ReadEnd::Delim),
+        Err(InputError::Interrupt) => (vec![], ReadEnd::Interrupted),
+        Err(_) => (vec![], ReadEnd::Eof),
+    }
+}
+
+fn read_input(core: &mut ShellCore, opts: &ReadOptions) -> (Vec<u8>, ReadEnd) {
+    let tty = unistd::isatty(opts.fd) == Ok(true);
+    if tty && opts.editor && opts.fd == 0 {
+        return read_with_editor(core, opts);
+    }
+
+    if let (true, Some(p)) = (tty, &opts.prompt) {
+        eprint!("{}", p);
+    }
+
+    let fd = unsafe { BorrowedFd::borrow_raw(opts.fd) };
+    let saved = match tty && (opts.silent || opts.nchars.is_some()) {
+        true => termios::tcgetattr(fd).ok(),
+        false => None,
+    };
+
+    if let Some(saved) = &saved {
+        let mut t = saved.clone();
+        if opts.silent {
+            t.local_flags.remove(LocalFlags::ECHO);
+        }
+        if opts.nchars.is_some() {
+            t.local_flags.remove(LocalFlags::ICANON);
+            t.control_chars[SpecialCharacterIndices::VMIN as usize] = 1;
+            t.control_chars[SpecialCharacterIndices::VTIME as usize] = 0;
+        }
+        let _ = termios::tcsetattr(fd, SetArg::TCSANOW, &t);
+    }
+
+    let ans = read_bytes(core, opts);
+
+    if let Some(saved) = &saved {
+        let _ = termios::tcsetattr(fd, SetArg::TCSANOW, saved);
+    }
+    ans
+}
+
+fn input_available(fd: RawFd) -> bool {
+    let fd = unsafe { BorrowedFd::borrow_raw(fd) };
+    let mut fds = [PollFd::new(fd, PollFlags::POLLIN)];
+    matches!(poll::poll(&mut fds, PollTimeout::ZERO), Ok(n) if n > 0)
+}
+
 pub fn read(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
     if args.len() < 1 {
         return 0;
     }
 
-    let mut args = arg::dissolve_options(args);
-    let r_opt = arg::consume_option("-r", &mut args);
-    let mut limit = std::usize::MAX;
-    let limit_str = arg::consume_with_next_arg("-n", &mut args);
-    let delim = match arg::consume_with_next_arg("-d", &mut args) {
-        Some(c) => c,
-        None => "\n".to_string(),
+    let (opts, names) = match ReadOptions::parse(core, args) {
+        Ok(ans) => ans,
+        Err(status) => return status,
     };
 
-    if limit_str.is_some() {
-        let s = limit_str.unwrap();
-        match s.parse::<usize>() {
-            Ok(n) => limit = n,
-            Err(_) => {
-                let err = format!("{}: invalid number", &s);
-                return error_exit(1, "read", &err, core);
-            },
-        };
+    if opts.timeout == Some(0.0) {
+        return if input_available(opts.fd) { 0 } else { 1 };
     }
 
-    if let Some(a) = arg::consume_with_next_arg("-a", &mut args) {
-        return read_a(core, &a, r_opt, &mut limit, &delim);
+    let (input, end) = read_input(core, &opts);
+    let line = String::from_utf8_lossy(&input).to_string();
+    let delim = (opts.delim as char).to_string();
+
+    if end == ReadEnd::Interrupted {
+        return 130;
     }
 
-    read_(core, &mut args, r_opt, &mut limit, &delim)
+    let status = if let Some(a) = &opts.array {
+        read_a(core, a, line, opts.raw, &delim)
+    } else if opts.exact || names.is_empty() {
+        set_reply(core, &names, &line, &opts)
+    } else {
+        read_(core, names, line, opts.raw, &delim)
+    };
+
+    match end {
+        _ if status != 0 => status,
+        ReadEnd::Eof => 1,
+        ReadEnd::Timeout => 128 + Signal::SIGALRM as i32,
+        _ => 0,
+    }
 }
 
-pub fn eat_word(
-    core: &mut ShellCore,
-    remaining: &mut String,
-    ifs: &str,
-    ignore_escape: bool,
-    delim: &String,
-) -> Option<String> {
+pub fn eat_word(remaining: &mut String, ifs: &str, ignore_escape: bool) -> String {
     let mut esc = false;
     let mut pos = 0;
     let mut escape_pos = vec![];
@@ -172,28 +446,15 @@ pub fn eat_word(
         pos += c.len_utf8();
     }
 
-    if let Some(p) = escape_pos.last() {
-        if p + 2 == remaining.len() && remaining.ends_with('\n') {
-            remaining.pop();
-            remaining.pop();
-
-            let line = utils::read_line_stdin_unbuffered(delim).unwrap_or("".to_string());
-            if line.len() > 0 {
-                *remaining += &line;
-                return eat_word(core, remaining, ifs, ignore_escape, delim);
-            }
-        }
-    }
-
     let tail = remaining.split_off(pos);
     let mut ans = remaining.clone();
     *remaining = tail;
 
-    for p in escape_pos {
+    for p in escape_pos.into_iter().rev() {
         ans.remove(p);
     }
 
-    Some(ans)
+    ans
 }
 
 pub fn consume_tail_ifs(remaining: &mut String, ifs: &str) {
@@ -208,13 +469,13 @@ pub fn consume_tail_ifs(remaining: &mut String, ifs: &str) {
     }
 }
 
-pub fn consume_ifs(remaining: &mut String, ifs: &str, limit: &mut usize) {
+pub fn consume_ifs(remaining: &mut String, ifs: &str) {
     let special_ifs: Vec<char> = ifs.chars().filter(|s| !" \t\n".contains(*s)).collect();
     let mut pos = 0;
     let mut special_ifs_exist = false;
 
     for ch in remaining.chars() {
-        if !ifs.contains(ch) || *limit == 0 {
+        if !ifs.contains(ch) {
             break;
         }
 
@@ -226,7 +487,6 @@ pub fn consume_ifs(remaining: &mut String, ifs: &str, limit: &mut usize) {
             special_ifs_exist = true;
         }
         pos += ch.len_utf8();
-        *limit -= 1;
     }
 
     let tail = remaining.split_off(pos);
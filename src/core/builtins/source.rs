@@ -31,24 +31,19 @@ pub fn source(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
     }
 
     let mut feeder = Feeder::new("");
-    if let Err(e) = feeder.set_file(&args[1]) {
-        ParseError::Input(e).print(core);
-        return 1;
-    }
-
-    let mut source = match core.db.get_vec("BASH_SOURCE", false) {
-        Ok(s) => s,
+    let fd = match feeder.set_file(&args[1]) {
+        Ok(fd) => fd,
         Err(e) => {
-            e.print(core);
+            ParseError::Input(e).print(core);
             return 1;
         },
     };
+    core.fds.set_internal(fd, "source");
 
     core.source_function_level += 1;
     core.source_files.push(args[1].to_string());
     core.db.position_parameters.push(args[1..].to_vec());
-    source.insert(0, args[1].clone());
-    let _ = core.db.set_array("BASH_SOURCE", Some(source.clone()), None);
+    core.push_call_stack("source", &args[1], false);
 
     feeder.main_feeder = true;
     loop {
@@ -70,11 +65,11 @@ pub fn source(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
         }
     }
 
-    source.remove(0);
-    let _ = core.db.set_array("BASH_SOURCE", Some(source), None);
+    core.pop_call_stack(false);
     core.db.position_parameters.pop();
     core.source_function_level -= 1;
     core.source_files.pop();
+    core.fds.remove_internal(fd);
     core.return_flag = false;
     core.db.exit_status
 }
@@ -10,7 +10,8 @@ use std::collections::HashMap;
 
 use self::data::{
     Data, array::ArrayData, array_int::IntArrayData, array_uninit::UninitArray, assoc::AssocData,
-    assoc_int::IntAssocData, assoc_uninit::UninitAssoc, single::SingleData, single_int::IntData,
+    assoc_int::IntAssocData, assoc_uninit::UninitAssoc, single::SingleData, single_float::FloatData,
+    single_int::IntData,
 };
 use crate::{elements::command::function_def::FunctionDefinition, env, error::exec::ExecError, exit};
 // use self::data::special::SpecialData;
@@ -123,11 +124,11 @@ impl DataBase {
         }
     }
 
-    pub fn int_to_str_type(&mut self, name: &str, layer: usize) -> Result<(), ExecError> {
+    pub fn drop_type_flag(&mut self, name: &str, flag: char, layer: usize) -> Result<(), ExecError> {
         let layer_len = self.param_options.len();
         for ly in layer..layer_len {
             if let Some(opt) = self.param_options[ly].get_mut(name) {
-                opt.retain(|c| c != 'i');
+                opt.retain(|c| c != flag);
             }
         }
 
@@ -12,6 +12,7 @@ pub mod epochseconds;
 pub mod random;
 pub mod seconds;
 pub mod single;
+pub mod single_float;
 pub mod single_int;
 // pub mod special;
 pub mod srandom;
@@ -20,13 +21,20 @@ use std::{fmt, fmt::Debug};
 
 use crate::error::{arith::ArithError, exec::ExecError};
 
-fn to_int(s: &str) -> Result<isize, ExecError> {
-    match s.parse::<isize>() {
+fn to_int(s: &str) -> Result<i128, ExecError> {
+    match s.parse::<i128>() {
         Ok(n) => Ok(n),
         Err(e) => return Err(ArithError::OperandExpected(e.to_string()).into()),
     }
 }
 
+fn add_int(left: i128, right: i128) -> Result<i128, ExecError> {
+    match left.checked_add(right) {
+        Some(n) => Ok(n),
+        None => Err(ArithError::Overflow(format!("{}+{}", left, right)).into()),
+    }
+}
+
 impl Debug for dyn Data {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         fmt.debug_struct(&self.print_body()).finish()
@@ -78,7 +86,7 @@ pub trait Data {
     fn append_as_single(&mut self, _: &str) -> Result<(), ExecError> {
         Err(ExecError::Other("Undefined call set_as_single".to_string()))
     }
-    fn get_as_single_num(&mut self) -> Result<isize, ExecError> {
+    fn get_as_single_num(&mut self) -> Result<i128, ExecError> {
         Err(ExecError::Other("not a single variable".to_string()))
     }
 
@@ -141,6 +149,12 @@ pub trait Data {
         Err(ExecError::Other("not an array".to_string()))
     }
 
+    fn get_vec_range(&mut self, pos: usize, len: usize) -> Result<Vec<String>, ExecError> {
+        let mut ans = self.get_vec_from(pos, true)?;
+        ans.truncate(len);
+        Ok(ans)
+    }
+
     fn get_all_indexes_as_array(&mut self) -> Result<Vec<String>, ExecError> {
         Err(ExecError::Other("not an array".to_string()))
     }
@@ -1,18 +1,18 @@
 // SPDXFileCopyrightText: 2024 Ryuichi Ueda ryuichiueda@gmail.com
 // SPDXLicense-Identifier: BSD-3-Clause
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use super::{Data, array_uninit::UninitArray};
 use crate::{error::exec::ExecError, utils};
 
 #[derive(Debug, Clone, Default)]
 pub struct ArrayData {
-    body: HashMap<usize, String>,
+    body: BTreeMap<usize, String>,
 }
 
-impl From<HashMap<usize, String>> for ArrayData {
-    fn from(h: HashMap<usize, String>) -> Self {
+impl From<BTreeMap<usize, String>> for ArrayData {
+    fn from(h: BTreeMap<usize, String>) -> Self {
         let mut ans = Self::default();
         ans.body = h;
         ans
@@ -36,9 +36,9 @@ impl Data for ArrayData {
 
     fn print_body(&self) -> String {
         let mut formatted = "(".to_string();
-        for i in self.keys() {
-            let ansi = utils::to_ansi_c(&self.body[&i]);
-            if ansi == self.body[&i] {
+        for (i, v) in &self.body {
+            let ansi = utils::to_ansi_c(v);
+            if ansi == *v {
                 formatted += &format!("[{}]=\"{}\" ", i, &ansi.replace("$", "\\$"));
             } else {
                 formatted += &format!("[{}]={} ", i, &ansi);
@@ -101,28 +101,24 @@ impl Data for ArrayData {
     }
 
     fn get_vec_from(&mut self, pos: usize, skip_non: bool) -> Result<Vec<String>, ExecError> {
-        if self.body.is_empty() {
-            return Ok(vec![]);
+        if skip_non {
+            return Ok(self.body.range(pos..).map(|e| e.1.clone()).collect());
         }
 
-        let keys = self.keys();
-        let max = *keys.iter().max().unwrap() as usize;
         let mut ans = vec![];
-        for i in pos..(max + 1) {
-            match self.body.get(&i) {
-                Some(s) => ans.push(s.clone()),
-                None => {
-                    if !skip_non {
-                        ans.push("".to_string());
-                    }
-                },
-            }
+        for (i, v) in self.body.range(pos..) {
+            ans.resize(i - pos, "".to_string());
+            ans.push(v.clone());
         }
         Ok(ans)
     }
 
+    fn get_vec_range(&mut self, pos: usize, len: usize) -> Result<Vec<String>, ExecError> {
+        Ok(self.body.range(pos..).take(len).map(|e| e.1.clone()).collect())
+    }
+
     fn get_all_indexes_as_array(&mut self) -> Result<Vec<String>, ExecError> {
-        Ok(self.keys().iter().map(|k| k.to_string()).collect())
+        Ok(self.body.keys().map(|k| k.to_string()).collect())
     }
 
     fn get_as_single(&mut self) -> Result<String, ExecError> {
@@ -145,8 +141,8 @@ impl Data for ArrayData {
     }
 
     fn index_based_len(&mut self) -> usize {
-        match self.body.iter().map(|e| e.0).max() {
-            Some(n) => *n + 1,
+        match self.body.last_key_value() {
+            Some((n, _)) => *n + 1,
             None => 0,
         }
     }
@@ -249,15 +245,7 @@ impl ArrayData {
     }
 
     pub fn values(&self) -> Vec<String> {
-        let mut keys: Vec<usize> = self.body.iter().map(|e| e.0.clone()).collect();
-        keys.sort();
-        keys.iter().map(|i| self.body[i].clone()).collect()
-    }
-
-    pub fn keys(&self) -> Vec<usize> {
-        let mut keys: Vec<usize> = self.body.iter().map(|e| e.0.clone()).collect();
-        keys.sort();
-        keys
+        self.body.values().cloned().collect()
     }
 
     fn to_index(&mut self, key: &str) -> Result<usize, ExecError> {
@@ -270,12 +258,7 @@ impl ArrayData {
             return Ok(index as usize);
         }
 
-        let keys = self.keys();
-        let max = match keys.iter().max() {
-            Some(n) => *n as isize,
-            None => -1,
-        };
-        index += max + 1;
+        index += self.index_based_len() as isize;
 
         if index < 0 {
             return Err(ExecError::ArrayIndexInvalid(key.to_string()));
@@ -1,14 +1,14 @@
 // SPDXFileCopyrightText: 2024 Ryuichi Ueda ryuichiueda@gmail.com
 // SPDXLicense-Identifier: BSD-3-Clause
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use super::{Data, array::ArrayData, array_uninit::UninitArray};
 use crate::error::exec::ExecError;
 
 #[derive(Debug, Clone, Default)]
 pub struct IntArrayData {
-    body: HashMap<usize, isize>,
+    body: BTreeMap<usize, i128>,
 }
 
 impl Data for IntArrayData {
@@ -18,8 +18,8 @@ impl Data for IntArrayData {
 
     fn print_body(&self) -> String {
         let mut formatted = "(".to_string();
-        for i in self.keys() {
-            formatted += &format!("[{}]=\"{}\" ", i, &self.body[&i]);
+        for (i, n) in &self.body {
+            formatted += &format!("[{}]=\"{}\" ", i, n);
         }
         if formatted.ends_with(" ") {
             formatted.pop();
@@ -50,13 +50,13 @@ impl Data for IntArrayData {
     }
 
     fn append_as_single(&mut self, value: &str) -> Result<(), ExecError> {
-        let n = match value.parse::<isize>() {
+        let n = match value.parse::<i128>() {
             Ok(n) => n,
             Err(e) => return Err(ExecError::Other(e.to_string())),
         };
 
         if let Some(v) = self.body.get(&0) {
-            self.body.insert(0, v + n);
+            self.body.insert(0, super::add_int(*v, n)?);
         } else {
             self.body.insert(0, n);
         }
@@ -88,7 +88,7 @@ impl Data for IntArrayData {
         let n = super::to_int(value)?;
 
         if let Some(prev) = self.body.get(&key) {
-            self.body.insert(key, prev + n);
+            self.body.insert(key, super::add_int(*prev, n)?);
         } else {
             self.body.insert(key, n);
         }
@@ -99,59 +99,33 @@ impl Data for IntArrayData {
         if key == "@" {
             return Ok(self.values().join(" "));
         }
-        if key == "@" {
+        if key == "*" {
             return Ok(self.values().join(ifs));
         }
 
-        let n = key.parse::<usize>().map_err(|_| ExecError::ArrayIndexInvalid(key.to_string()))?;
-
-        Ok(self.body.get(&n).unwrap_or(&0).to_string())
+        let n = self.to_index(key)?;
+        Ok(self.body.get(&n).map(|v| v.to_string()).unwrap_or_default())
     }
 
-    fn get_all_as_array(&mut self, skip_none: bool) -> Result<Vec<String>, ExecError> {
-        if self.body.is_empty() {
-            return Ok(vec![]);
+    fn get_vec_from(&mut self, pos: usize, skip_non: bool) -> Result<Vec<String>, ExecError> {
+        if skip_non {
+            return Ok(self.body.range(pos..).map(|e| e.1.to_string()).collect());
         }
 
-        let keys = self.keys();
-        let max = *keys.iter().max().unwrap() as usize;
         let mut ans = vec![];
-        for i in 0..(max + 1) {
-            match self.body.get(&i) {
-                Some(s) => ans.push(s.to_string()),
-                None => {
-                    if !skip_none {
-                        ans.push("".to_string());
-                    }
-                },
-            }
+        for (i, n) in self.body.range(pos..) {
+            ans.resize(i - pos, "".to_string());
+            ans.push(n.to_string());
         }
         Ok(ans)
     }
 
-    fn get_vec_from(&mut self, pos: usize, skip_non: bool) -> Result<Vec<String>, ExecError> {
-        if self.body.is_empty() {
-            return Ok(vec![]);
-        }
-
-        let keys = self.keys();
-        let max = *keys.iter().max().unwrap() as usize;
-        let mut ans = vec![];
-        for i in pos..(max + 1) {
-            match self.body.get(&i) {
-                Some(s) => ans.push(s.to_string()),
-                None => {
-                    if !skip_non {
-                        ans.push("".to_string());
-                    }
-                },
-            }
-        }
-        Ok(ans)
+    fn get_vec_range(&mut self, pos: usize, len: usize) -> Result<Vec<String>, ExecError> {
+        Ok(self.body.range(pos..).take(len).map(|e| e.1.to_string()).collect())
     }
 
     fn get_all_indexes_as_array(&mut self) -> Result<Vec<String>, ExecError> {
-        Ok(self.keys().iter().map(|k| k.to_string()).collect())
+        Ok(self.body.keys().map(|k| k.to_string()).collect())
     }
 
     fn get_as_single(&mut self) -> Result<String, ExecError> {
@@ -159,12 +133,8 @@ impl Data for IntArrayData {
     }
 
     fn get_str_type(&self) -> Box<dyn Data> {
-        let mut hash = HashMap::new();
-        for d in &self.body {
-            hash.insert(*d.0, d.1.to_string());
-        }
-
-        Box::new(ArrayData::from(hash))
+        let body = self.body.iter().map(|(k, v)| (*k, v.to_string())).collect::<BTreeMap<usize, String>>();
+        Box::new(ArrayData::from(body))
     }
 
     fn is_array(&self) -> bool {
@@ -174,13 +144,20 @@ impl Data for IntArrayData {
         self.body.len()
     }
 
+    fn index_based_len(&mut self) -> usize {
+        match self.body.last_key_value() {
+            Some((n, _)) => *n + 1,
+            None => 0,
+        }
+    }
+
     fn elem_len(&mut self, key: &str) -> Result<usize, ExecError> {
         if key == "@" || key == "*" {
             return Ok(self.len());
         }
 
-        let n = key.parse::<usize>().map_err(|_| ExecError::ArrayIndexInvalid(key.to_string()))?;
-        let s = self.body.get(&n).unwrap_or(&0).to_string();
+        let n = self.to_index(key)?;
+        let s = self.body.get(&n).map(|v| v.to_string()).unwrap_or_default();
 
         Ok(s.chars().count())
     }
@@ -191,11 +168,9 @@ impl Data for IntArrayData {
             return Ok(());
         }
 
-        if let Ok(n) = key.parse::<usize>() {
-            self.body.remove(&n);
-            return Ok(());
-        }
-        Err(ExecError::Other("invalid index".to_string()))
+        let n = self.to_index(key)?;
+        self.body.remove(&n);
+        Ok(())
     }
 }
 
@@ -239,15 +214,7 @@ impl IntArrayData {
     }
 
     pub fn values(&self) -> Vec<String> {
-        let mut keys: Vec<usize> = self.body.iter().map(|e| e.0.clone()).collect();
-        keys.sort();
-        keys.iter().map(|i| self.body[i].to_string()).collect()
-    }
-
-    pub fn keys(&self) -> Vec<usize> {
-        let mut keys: Vec<usize> = self.body.iter().map(|e| e.0.clone()).collect();
-        keys.sort();
-        keys
+        self.body.values().map(|n| n.to_string()).collect()
     }
 
     fn to_index(&mut self, key: &str) -> Result<usize, ExecError> {
@@ -260,12 +227,7 @@ impl IntArrayData {
             return Ok(index as usize);
         }
 
-        let keys = self.keys();
-        let max = match keys.iter().max() {
-            Some(n) => *n as isize,
-            None => -1,
-        };
-        index += max + 1;
+        index += self.index_based_len() as isize;
 
         if index < 0 {
             return Err(ExecError::ArrayIndexInvalid(key.to_string()));
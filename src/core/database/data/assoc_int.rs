@@ -8,7 +8,7 @@ use crate::{error::exec::ExecError, utils};
 
 #[derive(Debug, Clone, Default)]
 pub struct IntAssocData {
-    body: HashMap<String, isize>,
+    body: HashMap<String, i128>,
     last: Option<String>,
 }
 
@@ -66,7 +66,7 @@ impl Data for IntAssocData {
         let n = super::to_int(value)?;
 
         if let Some(v) = self.body.get(key) {
-            self.body.insert(key.to_string(), v + n);
+            self.body.insert(key.to_string(), super::add_int(*v, n)?);
         } else {
             self.body.insert(key.to_string(), n);
         }
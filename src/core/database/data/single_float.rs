@@ -0,0 +1,71 @@
+// SPDX-FileCopyrightText: 2024 Ryuichi Ueda ryuichiueda@gmail.com
+// SPDX-License-Identifier: BSD-3-Clause
+
+use super::{Data, single::SingleData};
+use crate::{
+    error::{arith::ArithError, exec::ExecError},
+    utils,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct FloatData {
+    pub body: f64,
+}
+
+fn to_float(s: &str) -> Result<f64, ExecError> {
+    match s.trim().parse::<f64>() {
+        Ok(f) if f.is_finite() => Ok(f),
+        _ => Err(ArithError::InvalidNumber(s.to_string()).into()),
+    }
+}
+
+impl Data for FloatData {
+    fn boxed_clone(&self) -> Box<dyn Data> {
+        Box::new(self.clone())
+    }
+    fn print_body(&self) -> String {
+        utils::to_ansi_c(&self.body.to_string())
+    }
+
+    fn clear(&mut self) {}
+
+    fn set_as_single(&mut self, value: &str) -> Result<(), ExecError> {
+        self.body = match value {
+            "" => 0.0,
+            v => to_float(v)?,
+        };
+        Ok(())
+    }
+
+    fn append_as_single(&mut self, value: &str) -> Result<(), ExecError> {
+        self.body += to_float(value)?;
+        Ok(())
+    }
+
+    fn init_as_num(&mut self) -> Result<(), ExecError> {
+        self.body = 0.0;
+        Ok(())
+    }
+
+    fn get_as_single(&mut self) -> Result<String, ExecError> {
+        Ok(self.body.to_string())
+    }
+
+    fn get_str_type(&self) -> Box<dyn Data> {
+        Box::new(SingleData::from(self.body.to_string().as_ref()))
+    }
+
+    fn len(&mut self) -> usize {
+        self.body.to_string().len()
+    }
+    fn is_single(&self) -> bool {
+        true
+    }
+
+    fn has_key(&mut self, key: &str) -> Result<bool, ExecError> {
+        if key == "@" || key == "*" {
+            return Ok(true);
+        }
+        Ok(key == "0")
+    }
+}
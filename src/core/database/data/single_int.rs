@@ -6,7 +6,7 @@ use crate::{error::exec::ExecError, utils};
 
 #[derive(Debug, Clone)]
 pub struct IntData {
-    pub body: isize,
+    pub body: i128,
 }
 
 impl Data for IntData {
@@ -20,7 +20,7 @@ impl Data for IntData {
     fn clear(&mut self) {}
 
     fn set_as_single(&mut self, value: &str) -> Result<(), ExecError> {
-        match value.parse::<isize>() {
+        match value.parse::<i128>() {
             Ok(n) => self.body = n,
             Err(e) => {
                 return Err(ExecError::Other(e.to_string()));
@@ -30,8 +30,8 @@ impl Data for IntData {
     }
 
     fn append_as_single(&mut self, value: &str) -> Result<(), ExecError> {
-        match value.parse::<isize>() {
-            Ok(n) => self.body += n,
+        match value.parse::<i128>() {
+            Ok(n) => self.body = super::add_int(self.body, n)?,
             Err(e) => {
                 return Err(ExecError::Other(e.to_string()));
             },
@@ -47,7 +47,7 @@ impl Data for IntData {
     fn get_as_single(&mut self) -> Result<String, ExecError> {
         Ok(self.body.to_string())
     }
-    fn get_as_single_num(&mut self) -> Result<isize, ExecError> {
+    fn get_as_single_num(&mut self) -> Result<i128, ExecError> {
         Ok(self.body)
     }
 
@@ -72,6 +72,10 @@ impl DataBase {
         self.has_flag(name, 'i')
     }
 
+    pub fn is_float(&mut self, name: &str) -> bool {
+        self.has_flag(name, 'E')
+    }
+
     pub fn write_check(&mut self, name: &str) -> Result<(), ExecError> {
         if self.has_flag(name, 'r') {
             return Err(ExecError::VariableReadOnly(name.to_string()));
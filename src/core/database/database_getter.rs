@@ -98,6 +98,13 @@ impl DataBase {
         }
     }
 
+    pub fn get_vec_range(&mut self, name: &str, pos: usize, len: usize) -> Result<Vec<String>, ExecError> {
+        match self.get_ref(name) {
+            Some(d) => Ok(d.get_vec_range(pos, len).unwrap_or_default()),
+            None => self.get_vec_from(name, pos, true),
+        }
+    }
+
     pub fn len(&mut self, name: &str) -> usize {
         if let Some(d) = self.get_ref(name) {
             return d.len();
@@ -8,13 +8,17 @@ use std::{env, process};
 use nix::unistd;
 
 use super::{
-    ArrayData, AssocData, Data, IntArrayData, IntAssocData, IntData, SingleData, UninitArray, UninitAssoc,
+    ArrayData, AssocData, Data, FloatData, IntArrayData, IntAssocData, IntData, SingleData, UninitArray, UninitAssoc,
     data::{
         epochrealtime::EpochRealTime, epochseconds::EpochSeconds, random::RandomVar, seconds::Seconds,
         srandom::SRandomVar,
     },
 };
-use crate::{core::DataBase, error::exec::ExecError, utils::restricted_shell};
+use crate::{
+    core::DataBase,
+    error::exec::ExecError,
+    utils::{c_string, restricted_shell},
+};
 
 impl DataBase {
     pub fn init_as_num(&mut self, name: &str, value: &str, layer: Option<usize>) -> Result<(), ExecError> {
@@ -34,7 +38,7 @@ impl DataBase {
         let mut data = IntData { body: 0 };
 
         if value != "" {
-            match value.parse::<isize>() {
+            match value.parse::<i128>() {
                 Ok(n) => data.body = n,
                 Err(e) => {
                     return Err(ExecError::Other(e.to_string()));
@@ -46,6 +50,25 @@ impl DataBase {
         Ok(())
     }
 
+    pub fn init_as_float(&mut self, name: &str, value: &str, layer: Option<usize>) -> Result<(), ExecError> {
+        Self::name_check(name)?;
+        self.write_check(name)?;
+        restricted_shell::check(self, name, &Some(vec![value.to_string()]))?;
+
+        let layer = self.get_target_layer(name, layer);
+        match self.param_options[layer].get_mut(name) {
+            Some(e) => *e += "E",
+            None => {
+                self.param_options[layer].insert(name.to_string(), "E".to_string());
+            },
+        }
+
+        let mut data = FloatData::default();
+        data.set_as_single(value)?;
+        self.params[layer].insert(name.to_string(), Box::new(data));
+        Ok(())
+    }
+
     pub fn set_param(&mut self, name: &str, val: &str, layer: Option<usize>) -> Result<(), ExecError> {
         Self::name_check(name)?;
         self.write_check(name)?;
@@ -64,15 +87,25 @@ impl DataBase {
             true => val.to_string().to_lowercase(),
             false => val.to_string(),
         };
+        let f_flag = self.has_flag(name, 'E');
         let layer = self.get_target_layer(name, layer);
         let db_layer = &mut self.params[layer];
 
-        if env::var(name).is_ok() {
-            env::set_var(name, &val);
+        if env::var_os(name).is_some() {
+            env::set_var(name, c_string::to_os_string(&val));
         }
 
-        if db_layer.get(name).is_none() {
-            db_layer.insert(name.to_string(), Box::new(SingleData::from("")));
+        match db_layer.get(name) {
+            None if f_flag => {
+                db_layer.insert(name.to_string(), Box::new(FloatData::default()));
+            },
+            None => {
+                db_layer.insert(name.to_string(), Box::new(SingleData::from("")));
+            },
+            Some(d) if f_flag && d.is_single() && !d.is_special() => {
+                db_layer.insert(name.to_string(), Box::new(FloatData::default()));
+            },
+            _ => {},
         }
 
         let d = db_layer.get_mut(name).unwrap();
@@ -106,7 +139,7 @@ impl DataBase {
 
         if !self.flags.contains('r')
             && (self.flags.contains('a') || self.has_flag(name, 'x'))
-            && !env::var(name).is_ok()
+            && env::var_os(name).is_none()
         {
             env::set_var(name, "");
         }
@@ -118,8 +151,9 @@ impl DataBase {
         let layer = self.get_target_layer(name, layer);
         let db_layer = &mut self.params[layer];
 
-        if let Ok(v) = env::var(name) {
-            env::set_var(name, v + &val);
+        if let Some(mut v) = env::var_os(name) {
+            v.push(c_string::to_os_string(&val));
+            env::set_var(name, v);
         }
 
         if db_layer.get(name).is_none() {
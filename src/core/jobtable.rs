@@ -1,13 +1,15 @@
 // SPDX-FileCopyrightText: 2023 Ryuichi Ueda ryuichiueda@gmail.com
 // SPDX-License-Identifier: BSD-3-Clause
 
+use std::os::fd::OwnedFd;
+
 use nix::{
     sys::{
         signal, wait,
         wait::{WaitPidFlag, WaitStatus},
     },
     unistd,
-    unistd::Pid,
+    unistd::{Pid, SysconfVar},
 };
 
 use crate::{ShellCore, error::exec::ExecError};
@@ -19,8 +21,11 @@ pub struct JobEntry {
     proc_statuses:      Vec<WaitStatus>,
     pub display_status: String,
     pub text:           String,
+    pub commands:       Vec<String>,
     change:             bool,
     pub no_control:     bool,
+    pub no_hup:         bool,
+    resume_fd:          Option<OwnedFd>,
 }
 
 fn wait_nonblock(pid: &Pid, status: &mut WaitStatus) -> Result<(), ExecError> {
@@ -45,6 +50,25 @@ fn wait_block(pid: &Pid, status: &mut WaitStatus) -> Result<i32, ExecError> {
     Ok(exit_status)
 }
 
+/* bounds of the saved status list, the same as bash's */
+const DEFAULT_CHILD_MAX: usize = 4096;
+const MAX_CHILD_MAX: usize = 32768;
+
+fn exit_status_of(status: &WaitStatus) -> Option<i32> {
+    match status {
+        WaitStatus::Exited(_, es) => Some(*es),
+        WaitStatus::Signaled(_, sig, _) => Some(*sig as i32 + 128),
+        _ => None,
+    }
+}
+
+fn child_max() -> usize {
+    match unistd::sysconf(SysconfVar::CHILD_MAX) {
+        Ok(Some(n)) if n > 0 => (n as usize).min(MAX_CHILD_MAX),
+        _ => DEFAULT_CHILD_MAX,
+    }
+}
+
 fn still(status: &WaitStatus) -> bool {
     match &status {
         WaitStatus::StillAlive => true,
@@ -61,15 +85,50 @@ impl JobEntry {
             pids: pids.into_iter().flatten().collect(),
             proc_statuses: statuses.to_vec(),
             display_status: status.to_string(),
-            text: text.to_string(),
+            text: text.trim_end().to_string(),
+            commands: vec![text.trim_end().to_string()],
+            change: true,
             ..Default::default()
         }
     }
 
+    /* The last process of the job continues a suspended compound command
+     * after the others, and receives their exit status through fd. */
+    pub fn new_suspended(pids: Vec<Pid>, statuses: &[WaitStatus], rest: Pid, fd: OwnedFd) -> JobEntry {
+        let mut proc_statuses = statuses.to_vec();
+        proc_statuses.push(WaitStatus::StillAlive);
+        JobEntry {
+            pids: pids.into_iter().chain([rest]).collect(),
+            proc_statuses,
+            display_status: "Stopped".to_string(),
+            change: true,
+            resume_fd: Some(fd),
+            ..Default::default()
+        }
+    }
+
+    pub fn waits_for_resume(&self) -> bool {
+        self.resume_fd.is_some()
+    }
+
+    fn resume_rest(&mut self) {
+        let n = self.proc_statuses.len() - 1;
+        if self.resume_fd.is_none() || self.proc_statuses[..n].iter().any(still) {
+            return;
+        }
+
+        let status = exit_status_of(&self.proc_statuses[n - 1]).unwrap_or(1);
+        if let Some(fd) = self.resume_fd.take() {
+            let _ = unistd::write(&fd, status.to_string().as_bytes());
+        }
+    }
+
     pub fn update_status(&mut self, wait: bool, check_done: bool) -> Result<i32, ExecError> {
         let mut exit_status = 0;
-        let before = self.proc_statuses[0];
-        for (status, pid) in self.proc_statuses.iter_mut().zip(&self.pids) {
+        let before = self.display_status.clone();
+        for i in 0..self.pids.len() {
+            self.resume_rest();
+            let (pid, status) = (&self.pids[i], &mut self.proc_statuses[i]);
             if still(status) {
                 match wait {
                     true => exit_status = wait_block(pid, status)?,
@@ -79,7 +138,7 @@ impl JobEntry {
                 }
             }
         }
-        self.change |= before != self.proc_statuses[0];
+        self.resume_rest();
 
         // check stopped processes
         let mut stopped = false;
@@ -99,52 +158,117 @@ impl JobEntry {
             }
         }
 
-        if stopped {
-            self.display_status = "Stopped".to_string();
-            return Ok(148);
+        self.change_display_status();
+        self.change |= before != self.display_status;
+
+        match stopped {
+            true => Ok(148),
+            false => Ok(exit_status),
         }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.display_status != "Stopped" && self.proc_statuses.iter().any(still)
+    }
 
-        if !stopped && self.display_status == "Stopped" || self.change {
-            self.change_display_status(self.proc_statuses[0]);
+    pub fn is_finished(&self) -> bool {
+        !self.proc_statuses.iter().any(still)
+    }
+
+    pub fn exit_status(&self) -> i32 {
+        match self.proc_statuses.last() {
+            Some(WaitStatus::Stopped(..)) => 148,
+            Some(s) => exit_status_of(s).unwrap_or(0),
+            None => 0,
         }
+    }
+
+    pub fn is_changed(&self) -> bool {
+        self.change
+    }
 
-        Ok(exit_status)
+    pub fn set_notified(&mut self) {
+        self.change = false;
     }
 
     pub fn print_p(&self) {
         println!("{}", self.pids[0]);
     }
 
-    pub fn print(&self, priority: &Vec<usize>, l_opt: bool, r_opt: bool, s_opt: bool, add_amp: bool) -> bool {
-        if r_opt && self.display_status != "Running" || s_opt && self.display_status != "Stopped" {
-            return false;
+    fn process_status(status: &WaitStatus) -> String {
+        match status {
+            WaitStatus::Stopped(_, signal::SIGSTOP) => "Stopped (signal)".to_string(),
+            WaitStatus::Stopped(_, signal::SIGTTIN) => "Stopped (tty input)".to_string(),
+            WaitStatus::Stopped(_, signal::SIGTTOU) => "Stopped (tty output)".to_string(),
+            WaitStatus::Stopped(..) => "Stopped".to_string(),
+            WaitStatus::Exited(_, 0) => "Done".to_string(),
+            WaitStatus::Exited(_, es) => format!("Exit {}", es),
+            WaitStatus::Signaled(_, signal, _) => Self::display_status_on_signal(signal, false),
+            _ => "Running".to_string(),
+        }
+    }
+
+    fn status_column(&self, status: &WaitStatus, long: bool) -> String {
+        let text = match (self.is_running(), long) {
+            (true, _) => "Running".to_string(),
+            (false, false) if self.display_status == "Stopped" => "Stopped".to_string(),
+            _ => Self::process_status(status),
+        };
+
+        match status {
+            WaitStatus::Signaled(_, _, true) if !self.is_running() => format!("{:<24}(core dumped) ", text),
+            _ => format!("{:<24}", text),
         }
+    }
 
-        let symbol = if priority[0] == self.id {
+    /* bash's format: one line per job, or one line per process with -l */
+    pub fn print(&self, priority: &Vec<usize>, long: bool) {
+        let symbol = if priority.first() == Some(&self.id) {
             "+"
-        } else if priority.len() > 1 && priority[1] == self.id {
+        } else if priority.get(1) == Some(&self.id) {
             "-"
         } else {
             " "
         };
-
-        let pid = match l_opt {
-            true => &self.pids[0].to_string(),
+        let amp = match self.is_running() {
+            true => " &",
             false => "",
         };
+        let head = format!("[{}]{} ", self.id, symbol);
+
+        if !long {
+            let last = self.proc_statuses.last().unwrap_or(&WaitStatus::StillAlive);
+            let text = match self.commands.len() {
+                1 => self.text.clone(),
+                _ => self.commands.join(" | "),
+            };
+            println!("{} {}{}{}", &head, &self.status_column(last, false), &text, amp);
+            return;
+        }
 
-        let tmp = self.text.clone();
-        let text = tmp.trim_end();
-
-        if self.display_status == "Stopped" {
-            println!("[{}]{} {} {}                 {}", self.id, &symbol, &pid, &self.display_status, &text);
-        } else if add_amp && self.display_status != "Done" {
-            println!("[{}]{} {} {}                 {} &", self.id, &symbol, &pid, &self.display_status, &text);
-        } else {
-            println!("[{}]{} {} {}                    {}", self.id, &symbol, &pid, &self.display_status, &text);
+        if self.commands.len() != self.pids.len() {
+            let first = self.proc_statuses.first().unwrap_or(&WaitStatus::StillAlive);
+            println!("{}{:5} {}{}{}", &head, self.pids[0], &self.status_column(first, true), &self.text, amp);
+            return;
         }
 
-        self.display_status == "Done" || self.display_status == "Killed"
+        let first = self.status_column(&self.proc_statuses[0], true);
+        for (i, pid) in self.pids.iter().enumerate() {
+            let end = match i + 1 == self.pids.len() {
+                true => amp,
+                false => "",
+            };
+            if i == 0 {
+                println!("{}{:5} {}{}{}", &head, pid, &first, &self.commands[i], end);
+                continue;
+            }
+
+            let status = match self.status_column(&self.proc_statuses[i], true) {
+                s if s == first => " ".repeat(22),
+                s => s,
+            };
+            println!("     {:5} {}| {}{}", pid, &status, &self.commands[i], end);
+        }
     }
 
     fn display_status_on_signal(signal: &signal::Signal, coredump: bool) -> String {
@@ -182,12 +306,19 @@ impl JobEntry {
         (msg.to_owned() + coredump_msg).to_string()
     }
 
-    fn change_display_status(&mut self, after: WaitStatus) {
-        self.display_status = match after {
-            WaitStatus::Exited(..) => "Done".to_string(),
-            WaitStatus::Stopped(..) => "Stopped".to_string(),
-            WaitStatus::Continued(_) => "Running".to_string(),
-            WaitStatus::Signaled(_, signal, coredump) => Self::display_status_on_signal(&signal, coredump),
+    fn change_display_status(&mut self) {
+        if self.proc_statuses.iter().any(|s| matches!(s, WaitStatus::Stopped(..))) {
+            self.display_status = "Stopped".to_string();
+            return;
+        }
+        if self.proc_statuses.iter().any(still) {
+            self.display_status = "Running".to_string();
+            return;
+        }
+
+        self.display_status = match self.proc_statuses.last() {
+            Some(WaitStatus::Exited(..)) => "Done".to_string(),
+            Some(WaitStatus::Signaled(_, signal, coredump)) => Self::display_status_on_signal(signal, *coredump),
             _ => return,
         }
     }
@@ -198,6 +329,16 @@ impl JobEntry {
         }
     }
 
+    pub fn send_hup(&mut self) {
+        for pid in &self.pids {
+            let _ = signal::kill(Pid::from_raw(-i32::from(*pid)), signal::SIGHUP)
+                .or_else(|_| signal::kill(*pid, signal::SIGHUP));
+        }
+        if self.display_status == "Stopped" {
+            self.send_cont();
+        }
+    }
+
     pub fn solve_pgid(&self) -> Pid {
         for pid in &self.pids {
             match unistd::getpgid(Some(*pid)) {
@@ -231,22 +372,65 @@ impl ShellCore {
         Ok(())
     }
 
+    pub fn jobtable_has_status_change(&self) -> bool {
+        !self.is_subshell && self.job_table.iter().any(|e| e.change && !e.is_running())
+    }
+
+    pub fn jobtable_hangup(&mut self) {
+        if self.is_subshell {
+            return;
+        }
+
+        self.job_table.iter_mut().filter(|e| !e.no_hup).for_each(|e| e.send_hup());
+    }
+
     pub fn jobtable_print_status_change(&mut self) {
         if self.is_subshell {
             return;
         }
 
         for e in self.job_table.iter_mut() {
-            if e.change {
-                e.print(&self.job_table_priority, false, false, false, false);
+            if e.change && !e.is_running() {
+                e.print(&self.job_table_priority, false);
                 e.change = false;
             }
         }
 
-        self.job_table.retain(|e| still(&e.proc_statuses[0]) || e.display_status == "Stopped");
+        let mut pos = self.job_table.len();
+        while pos > 0 {
+            pos -= 1;
+            if self.job_table[pos].is_finished() && !self.job_table[pos].change {
+                self.jobtable_remove(pos);
+            }
+        }
+    }
+
+    /* removes a job, keeping the exit statuses of its reaped processes for wait */
+    pub fn jobtable_remove(&mut self, pos: usize) {
+        let job = self.job_table.remove(pos);
+        self.job_table_priority.retain(|id| *id != job.id);
+
+        for (pid, status) in job.pids.iter().zip(job.proc_statuses.iter()) {
+            if let Some(es) = exit_status_of(status) {
+                self.saved_statuses.retain(|s| s.0 != *pid);
+                self.saved_statuses.push((*pid, es));
+            }
+        }
+
+        let max = child_max();
+        if self.saved_statuses.len() > max {
+            let over = self.saved_statuses.len() - max;
+            self.saved_statuses.drain(..over);
+        }
+    }
+
+    pub fn jobtable_saved_status(&self, pid: Pid) -> Option<i32> {
+        self.saved_statuses.iter().find(|s| s.0 == pid).map(|s| s.1)
+    }
 
-        let ids = self.job_table.iter().map(|j| j.id).collect::<Vec<usize>>();
-        self.job_table_priority.retain(|id| ids.contains(id));
+    /* a new child may reuse the pid of a reaped one */
+    pub fn jobtable_forget_saved_status(&mut self, pids: &[Pid]) {
+        self.saved_statuses.retain(|s| !pids.contains(&s.0));
     }
 
     pub fn generate_new_job_id(&self) -> usize {
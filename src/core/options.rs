@@ -20,6 +20,7 @@ impl Options {
         options.opts.insert("noglob".to_string(), false);
         options.opts.insert("onecmd".to_string(), false);
         options.opts.insert("posix".to_string(), false);
+        options.opts.insert("notify".to_string(), false);
         options.opts.insert("history".to_string(), false); //TODO: still dummy
         options
     }
@@ -68,8 +69,12 @@ impl Options {
             "restricted_shell",
             "shift_verbose",
             "sourcepath",
+            "varredir_close",
             "xpg_echo",
             "assoc_expand_once",
+            "arith_wrap",
+            "arith_checked",
+            "arith_float",
         ];
 
         for opt in opt_strs {
@@ -91,14 +96,20 @@ impl Options {
             "nocasematch",
             "expand_aliases",
             "xpg_echo",
+            "checkjobs",
+            "huponexit",
             "lastpipe",
             "execfail",
+            "varredir_close",
             "assoc_expand_once",
+            "arith_wrap",
+            "arith_checked",
+            "arith_float",
         ]
         .iter()
         .map(|s| s.to_string())
         .collect();
-        // TODO: nocasematch and xpg_echo are dummy
+        // TODO: nocasematch is dummy
 
         options
     }
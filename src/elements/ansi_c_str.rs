@@ -129,17 +129,6 @@ pub struct AnsiCString {
 }
 
 impl AnsiCString {
-    pub fn eval(&mut self) -> String {
-        let mut ans = String::new();
-        for t in &mut self.tokens {
-            if let AnsiCToken::EmptyHex = t {
-                break;
-            }
-            ans += &t.to_string();
-        }
-        ans
-    }
-
     fn eat_simple_subword(feeder: &mut Feeder, ans: &mut Self) -> bool {
         if let Some(a) = SimpleSubword::parse(feeder) {
             ans.text += a.get_text();
@@ -252,14 +241,12 @@ impl AnsiCString {
         true
     }
 
-    fn eat_escaped_char(feeder: &mut Feeder, ans: &mut Self, core: &mut ShellCore, for_echo: bool) -> bool {
+    fn eat_escaped_char(feeder: &mut Feeder, ans: &mut Self, core: &mut ShellCore) -> bool {
         if let Some(a) = EscapedChar::parse(feeder, core) {
             let txt = a.get_text().to_string();
             ans.text += &txt.clone();
 
-            if for_echo && txt == "\\'" {
-                ans.tokens.push(AnsiCToken::Normal(txt));
-            } else if txt != "\\c" || feeder.len() == 0 {
+            if txt != "\\c" || feeder.len() == 0 {
                 ans.tokens.push(AnsiCToken::OtherEscaped(txt[1..].to_string()));
             } else {
                 if let Some(a) = EscapedChar::parse(feeder, core) {
@@ -282,11 +269,11 @@ impl AnsiCString {
         }
     }
 
-    pub fn parse(feeder: &mut Feeder, core: &mut ShellCore, for_echo: bool) -> Result<Option<Self>, ParseError> {
+    pub fn parse(feeder: &mut Feeder, core: &mut ShellCore) -> Result<Option<Self>, ParseError> {
         let mut ans = Self::default();
 
         loop {
-            if !for_echo && feeder.starts_with("'") {
+            if feeder.starts_with("'") {
                 break;
             }
 
@@ -296,15 +283,12 @@ impl AnsiCString {
                 || Self::eat_oct(feeder, &mut ans, core)
                 || Self::eat_unicode4(feeder, &mut ans, core)
                 || Self::eat_unicode8(feeder, &mut ans, core)
-                || Self::eat_escaped_char(feeder, &mut ans, core, for_echo)
+                || Self::eat_escaped_char(feeder, &mut ans, core)
             {
                 continue;
             }
 
             if feeder.len() == 0 {
-                if for_echo {
-                    break;
-                }
                 feeder.feed_additional_line(core)?;
                 continue;
             }
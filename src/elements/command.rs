@@ -52,7 +52,10 @@ pub trait Command {
             self.fork_exec(core, pipe)
         } else {
             pipe.connect_lastpipe();
-            self.nofork_exec(core)
+            let proc_sub_num = core.fds.proc_sub.len();
+            let ans = self.nofork_exec(core);
+            io::close_proc_sub_fds(core, proc_sub_num);
+            ans
         }
     }
 
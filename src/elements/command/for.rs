@@ -93,7 +93,7 @@ impl ForCommand {
             if core.sigint.load(Relaxed) {
                 return false;
             }
-            if core.return_flag {
+            if core.return_flag || core.suspended_job.is_some() {
                 return false;
             }
 
@@ -136,7 +136,7 @@ impl ForCommand {
             return false;
         }
 
-        while !core.return_flag {
+        while !core.return_flag && core.suspended_job.is_none() {
             if core.sigint.load(Relaxed) {
                 return false;
             }
@@ -67,12 +67,7 @@ impl FunctionDefinition {
     }
 
     pub fn run_as_command(&mut self, args: &mut Vec<String>, core: &mut ShellCore) {
-        let mut array = core.db.get_vec("FUNCNAME", false).unwrap();
-        array.insert(0, args[0].clone()); //TODO: We must put the name not only in 0 but also 1..
-        let _ = core.db.set_array("FUNCNAME", Some(array.clone()), None);
-        let mut source = core.db.get_vec("BASH_SOURCE", false).unwrap();
-        source.insert(0, self.file.clone());
-        let _ = core.db.set_array("BASH_SOURCE", Some(source.clone()), None);
+        core.push_call_stack(&args[0], &self.file, true);
 
         args[0] = core.db.position_parameters[0][0].clone();
         core.db.position_parameters.push(args.to_vec());
@@ -87,11 +82,7 @@ impl FunctionDefinition {
         core.source_function_level -= 1;
 
         core.db.position_parameters.pop();
-
-        array.remove(0);
-        source.remove(0);
-        let _ = core.db.set_array("FUNCNAME", Some(array), None);
-        let _ = core.db.set_array("BASH_SOURCE", Some(source), None);
+        core.pop_call_stack(true);
     }
 
     fn eat_header(&mut self, feeder: &mut Feeder, core: &mut ShellCore) -> bool {
@@ -156,9 +147,11 @@ impl FunctionDefinition {
 
         if ans.command.is_some() {
             feeder.pop_backup();
-            if let Some(f) = core.source_files.last() {
-                ans.file = f.clone();
-            }
+            ans.file = match core.source_files.last() {
+                Some(f) => f.clone(),
+                None if core.script_name != "-" => core.script_name.clone(),
+                None => String::new(),
+            };
             Ok(Some(ans))
         } else {
             feeder.rewind();
@@ -13,7 +13,7 @@ use nix::unistd::Pid;
 use super::{Command, Pipe, Redirect};
 use crate::{
     ShellCore,
-    elements::{substitution::Substitution, word::Word},
+    elements::{io, substitution::Substitution, word::Word},
     env,
     error::exec::ExecError,
     proc_ctrl,
@@ -51,21 +51,10 @@ impl Command for SimpleCommand {
 
         core.db.set_param("BASH_COMMAND", &self.text, None)?;
 
-        self.args.clear();
-        let mut words = self.words.to_vec();
-        for w in words.iter_mut() {
-            self.set_arg(w, core)?;
-        }
-
-        if !self.args.is_empty() && self.args[0].starts_with("%") {
-            self.redirects.clear();
-            self.args.insert(0, "fg".to_string());
-        }
-
-        match self.args.len() {
-            0 => self.exec_set_param(core),
-            _ => self.exec_command(core, pipe),
-        }
+        let proc_sub_num = core.fds.proc_sub.len();
+        let ans = self.exec_with_args(core, pipe);
+        io::close_proc_sub_fds(core, proc_sub_num);
+        ans
     }
 
     fn run(&mut self, core: &mut ShellCore, fork: bool) -> Result<(), ExecError> {
@@ -104,6 +93,24 @@ impl Command for SimpleCommand {
 }
 
 impl SimpleCommand {
+    fn exec_with_args(&mut self, core: &mut ShellCore, pipe: &mut Pipe) -> Result<Option<Pid>, ExecError> {
+        self.args.clear();
+        let mut words = self.words.to_vec();
+        for w in words.iter_mut() {
+            self.set_arg(w, core)?;
+        }
+
+        if !self.args.is_empty() && self.args[0].starts_with("%") {
+            self.redirects.clear();
+            self.args.insert(0, "fg".to_string());
+        }
+
+        match self.args.len() {
+            0 => self.exec_set_param(core),
+            _ => self.exec_command(core, pipe),
+        }
+    }
+
     fn break_continue_or_return(core: &mut ShellCore) -> bool {
         core.break_counter > 0 || core.continue_counter > 0
     }
@@ -131,7 +138,9 @@ impl SimpleCommand {
             self.fork_exec(core, pipe)
         } else if self.args.len() == 1 && self.args[0] == "exec" {
             for r in self.get_redirects().iter_mut() {
-                if let Err(e) = r.connect(true, core) {
+                let result = r.connect(false, core);
+                r.changed_fds().into_iter().for_each(|fd| core.fds.update_user(fd));
+                if let Err(e) = result {
                     e.print(core);
                     core.db.exit_status = 1;
                     break;
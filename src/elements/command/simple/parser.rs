@@ -57,6 +57,10 @@ impl SimpleCommand {
     }
 
     fn eat_word(feeder: &mut Feeder, ans: &mut SimpleCommand, core: &mut ShellCore) -> Result<bool, ParseError> {
+        if ans.words.is_empty() && feeder.starts_with("}") && feeder.nest.last().is_some_and(|n| n.0 == "${") {
+            return Ok(false); // end of ${ command; }
+        }
+
         let mut mode = None;
         if ans.command_name == "eval" || ans.command_name == "let" {
             mode = Some(WordMode::EvalLet);
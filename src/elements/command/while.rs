@@ -20,11 +20,11 @@ pub struct WhileCommand {
 
 impl Command for WhileCommand {
     fn run(&mut self, core: &mut ShellCore, _: bool) -> Result<(), ExecError> {
-        if core.return_flag {
+        if core.return_flag || core.suspended_job.is_some() {
             return Ok(());
         }
         core.loop_level += 1;
-        while !core.return_flag {
+        while !core.return_flag && core.suspended_job.is_none() {
             core.suspend_e_option = true;
             self.while_script.clone().as_mut().unwrap().exec(core)?;
 
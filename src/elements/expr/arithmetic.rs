@@ -75,7 +75,7 @@ impl ArithmeticExpr {
                 Ok(ans) => Ok(ans),
                 Err(a) => return Err(ExecError::ArithError(cp.text, a)),
             },
-            ArithElem::Float(f) => Ok(f.to_string()),
+            ArithElem::Float(f) => elem::float::to_output(f, core),
             e => return Err(ExecError::ArithError(cp.text, ArithError::OperandExpected(e.to_string()).into())),
         }
     }
@@ -123,11 +123,11 @@ impl ArithmeticExpr {
             return Err(ArithError::InvalidBase(base_str));
         }
 
-        let mut tmp = n.abs();
+        let mut tmp = n.unsigned_abs();
         let mut digits = vec![];
         while tmp != 0 {
-            digits.insert(0, (tmp % base) as u8);
-            tmp /= base;
+            digits.insert(0, (tmp % base as u128) as u8);
+            tmp /= base as u128;
         }
 
         let mut ans = Self::dec_to_str(&digits, base);
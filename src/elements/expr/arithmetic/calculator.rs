@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: BSD-3-Clause
 
 use super::{
-    elem::{ArithElem, float, int, ternary, variable},
+    elem::{ArithElem, float, int, int::OverflowMode, ternary, variable},
     rev_polish,
 };
 use crate::{
@@ -57,13 +57,13 @@ fn bin_calc_and_or(op: &str, stack: &mut Vec<ArithElem>, core: &mut ShellCore) -
 
     left.change_to_value(0, core)?;
 
-    if let ArithElem::Integer(n) = left {
-        if n == 0 && op == "&&" {
+    if let Some(b) = to_bool(&left) {
+        if !b && op == "&&" {
             stack.push(ArithElem::Integer(0));
             return Ok(());
         }
 
-        if n != 0 && op == "||" {
+        if b && op == "||" {
             stack.push(ArithElem::Integer(1));
             return Ok(());
         }
@@ -71,16 +71,20 @@ fn bin_calc_and_or(op: &str, stack: &mut Vec<ArithElem>, core: &mut ShellCore) -
 
     right.change_to_value(0, core)?;
 
-    if let ArithElem::Integer(n) = right {
-        if n == 0 {
-            stack.push(ArithElem::Integer(0));
-        } else {
-            stack.push(ArithElem::Integer(1));
-        }
+    if let Some(b) = to_bool(&right) {
+        stack.push(ArithElem::Integer(b as i128));
     }
     Ok(())
 }
 
+fn to_bool(e: &ArithElem) -> Option<bool> {
+    match e {
+        ArithElem::Integer(n) => Some(*n != 0),
+        ArithElem::Float(f) => Some(*f != 0.0),
+        _ => None,
+    }
+}
+
 fn bin_calc_operation(op: &str, stack: &mut Vec<ArithElem>, core: &mut ShellCore) -> Result<(), ExecError> {
     let (left, right) = pop_operands(stack, core)?;
 
@@ -93,7 +97,7 @@ fn bin_calc_operation(op: &str, stack: &mut Vec<ArithElem>, core: &mut ShellCore
         (ArithElem::Float(fl), ArithElem::Float(fr)) => float::bin_calc(op, fl, fr, stack)?,
         (ArithElem::Float(fl), ArithElem::Integer(nr)) => float::bin_calc(op, fl, nr as f64, stack)?,
         (ArithElem::Integer(nl), ArithElem::Float(fr)) => float::bin_calc(op, nl as f64, fr, stack)?,
-        (ArithElem::Integer(nl), ArithElem::Integer(nr)) => int::bin_calc(op, nl, nr, stack)?,
+        (ArithElem::Integer(nl), ArithElem::Integer(nr)) => int::bin_calc(op, nl, nr, stack, OverflowMode::get(core))?,
         _ => exit::internal("invalid operand"),
     };
 
@@ -108,7 +112,7 @@ fn unary_operation(op: &str, stack: &mut Vec<ArithElem>, core: &mut ShellCore) -
 
     match operand {
         ArithElem::Float(num) => float::unary_calc(op, num, stack),
-        ArithElem::Integer(num) => int::unary_calc(op, num, stack),
+        ArithElem::Integer(num) => int::unary_calc(op, num, stack, OverflowMode::get(core)),
         _ => exit::internal("unknown operand"),
     }
 }
@@ -121,10 +125,30 @@ pub fn calculate(elements: &Vec<ArithElem>, core: &mut ShellCore) -> Result<Arit
     let rev_pol = rev_polish::rearrange(elements)?;
     dry_run(&rev_pol)?;
 
-    let mut stack = vec![];
+    let mut stack: Vec<ArithElem> = vec![];
     let mut escaped_unaries = vec![];
+    let seq_points = sequence_points(&rev_pol);
+    let mut skip_to = 0;
+
+    for (i, e) in rev_pol.into_iter().enumerate() {
+        if i < skip_to {
+            continue;
+        }
+
+        if let Some((op, (_, end))) = seq_points.iter().find(|(_, (start, _))| *start == i) {
+            if let Some(mut left) = stack.pop() {
+                left.change_to_value(0, core)?;
+                match (op.as_str(), to_bool(&left)) {
+                    ("&&", Some(false)) | ("||", Some(true)) => {
+                        stack.push(ArithElem::Integer((op == "||") as i128));
+                        skip_to = end + 1;
+                        continue;
+                    },
+                    _ => stack.push(left),
+                }
+            }
+        }
 
-    for e in rev_pol {
         match e {
             ArithElem::BinaryOp(ref op) => bin_operation(&op, &mut stack, core)?,
             ArithElem::UnaryOp(ref op) => match stack.is_empty() {
@@ -154,7 +178,41 @@ pub fn calculate(elements: &Vec<ArithElem>, core: &mut ShellCore) -> Result<Arit
     if stack.len() != 1 {
         return Err(ArithError::OperandExpected(stack.last().unwrap().to_string()).into());
     }
-    pop_operand(&mut stack, core)
+
+    match pop_operand(&mut stack, core)? {
+        ArithElem::Integer(n) => Ok(ArithElem::Integer(OverflowMode::get(core).fit(n, &n.to_string())?)),
+        e => Ok(e),
+    }
+}
+
+/* positions (start, end) of the right operands of binary operators in the
+ * reverse polish notation. The left operand is evaluated at the start so as
+ * to keep the order of side effects, and the right operand of && or || is
+ * skipped when it is not required. */
+fn sequence_points(rev_pol: &[ArithElem]) -> Vec<(String, (usize, usize))> {
+    let mut ans = vec![];
+
+    for (end, e) in rev_pol.iter().enumerate() {
+        let op = match e {
+            ArithElem::BinaryOp(op) if e.order() != 2 => op,
+            _ => continue,
+        };
+
+        let mut need = 1;
+        for start in (0..end).rev() {
+            match &rev_pol[start] {
+                ArithElem::BinaryOp(_) => need += 1,
+                ArithElem::UnaryOp(_) | ArithElem::Increment(_) | ArithElem::Ternary(..) => {},
+                _ => need -= 1,
+            }
+
+            if need == 0 {
+                ans.push((op.clone(), (start, end)));
+                break;
+            }
+        }
+    }
+    ans
 }
 
 fn dry_run(rev_pol: &Vec<ArithElem>) -> Result<(), ArithError> {
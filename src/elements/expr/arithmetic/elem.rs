@@ -23,6 +23,7 @@ pub enum ArithElem {
     Ternary(Box<Option<ArithmeticExpr>>, Box<Option<ArithmeticExpr>>),
     Variable(String, Option<Subscript>, i128), // name + subscript + post increment or decrement
     InParen(ArithmeticExpr),
+    Function(String, Vec<ArithmeticExpr>), // math function with arguments (arith_float)
     Increment(i128), // pre increment
     //    Delimiter(String), //delimiter dividing left and right of &&, ||, and ','
     // only for parse
@@ -67,6 +68,10 @@ impl ArithElem {
             ArithElem::Space(s) => s.to_string(),
             ArithElem::Symbol(s) => s.to_string(),
             ArithElem::InParen(a) => a.text.to_string(),
+            ArithElem::Function(name, args) => {
+                let args: Vec<String> = args.iter().map(|a| a.text.clone()).collect();
+                format!("{}({})", name, args.join(","))
+            },
             ArithElem::Integer(n) => n.to_string(),
             ArithElem::Float(f) => {
                 let mut ans = f.to_string();
@@ -123,6 +128,7 @@ impl ArithElem {
     pub fn change_to_value(&mut self, add: i128, core: &mut ShellCore) -> Result<(), ExecError> {
         *self = match self {
             ArithElem::InParen(ref mut a) => a.eval_elems(core, false)?,
+            ArithElem::Function(name, args) => float::call(name, args, core)?,
             ArithElem::Variable(name, s, inc) => {
                 if add != 0 && *inc != 0 || !utils::is_name(&name, core) {
                     return Err(ArithError::OperandExpected(name.to_string()).into());
@@ -150,7 +156,8 @@ impl ArithElem {
             | ArithElem::ArrayElem(..)
             | ArithElem::Word(..)
             | ArithElem::Variable(..)
-            | ArithElem::InParen(_) => true,
+            | ArithElem::InParen(_)
+            | ArithElem::Function(..) => true,
             _ => false,
         }
     }
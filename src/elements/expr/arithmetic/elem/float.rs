@@ -1,16 +1,65 @@
 // SPDX-FileCopyrightText: 2024 Ryuichi Ueda ryuichiueda@gmail.com
 // SPDX-License-Identifier: BSD-3-Clause
 
-use super::{ArithElem, variable};
+use super::{ArithElem, ArithmeticExpr, variable};
 use crate::{
-    ShellCore, error,
+    ShellCore,
     error::{arith::ArithError, exec::ExecError},
 };
 
+pub const FUNCTIONS: [&str; 13] =
+    ["abs", "ceil", "cos", "exp", "floor", "int", "log", "max", "min", "round", "sin", "sqrt", "tan"];
+
+pub fn enabled(core: &ShellCore) -> bool {
+    core.shopts.query("arith_float")
+}
+
+/* The same error as Bash when floats are not enabled */
+pub fn disabled_error(num: &str) -> ExecError {
+    let token = match num.find('.') {
+        Some(pos) => num[pos..].to_string(),
+        None => num.to_string(),
+    };
+    ExecError::ArithError(num.to_string(), ArithError::InvalidOperator(token))
+}
+
+/* SUSH_FLOAT_FORMAT takes a printf conversion like %.3f or %g */
+pub fn to_output(f: f64, core: &mut ShellCore) -> Result<String, ExecError> {
+    let fmt = core.db.get_param("SUSH_FLOAT_FORMAT").unwrap_or_default();
+    if fmt.is_empty() {
+        return Ok(f.to_string());
+    }
+
+    if !is_float_format(&fmt) {
+        let msg = format!("SUSH_FLOAT_FORMAT: {}: invalid float format", &fmt);
+        return Err(ExecError::Other(msg));
+    }
+
+    match sprintf::sprintf!(&fmt, f) {
+        Ok(s) => Ok(s),
+        Err(_) => Err(ExecError::Other(format!("SUSH_FLOAT_FORMAT: {}: invalid float format", &fmt))),
+    }
+}
+
+/* %[flags][width][.precision] and one of eEfFgG */
+fn is_float_format(fmt: &str) -> bool {
+    let s = match fmt.strip_prefix('%') {
+        Some(s) => s.trim_start_matches(['-', '+', ' ', '#', '0']),
+        None => return false,
+    };
+    let s = s.trim_start_matches(|c: char| c.is_ascii_digit());
+    let s = match s.strip_prefix('.') {
+        Some(s) => s.trim_start_matches(|c: char| c.is_ascii_digit()),
+        None => s,
+    };
+    matches!(s, "e" | "E" | "f" | "F" | "g" | "G")
+}
+
 pub fn unary_calc(op: &str, num: f64, stack: &mut Vec<ArithElem>) -> Result<(), ExecError> {
     match op {
         "+" => stack.push(ArithElem::Float(num)),
         "-" => stack.push(ArithElem::Float(-num)),
+        "!" => stack.push(ArithElem::Integer(if num == 0.0 { 1 } else { 0 })),
         _ => return Err(ExecError::Other("not supported operator for float number".to_string())),
     }
     Ok(())
@@ -31,20 +80,10 @@ pub fn bin_calc(op: &str, left: f64, right: f64, stack: &mut Vec<ArithElem>) ->
         ">" => stack.push(bool_to_01(left > right)),
         "==" => stack.push(bool_to_01(left == right)),
         "!=" => stack.push(bool_to_01(left != right)),
-        "/" => {
-            if right == 0.0 {
-                return Err(ExecError::Other("divided by 0".to_string()));
-            }
-            stack.push(ArithElem::Float(left / right));
-        },
-        "**" => {
-            if right >= 0.0 {
-                let r = right.try_into().unwrap();
-                stack.push(ArithElem::Float(left.powf(r)));
-            } else {
-                return Err(ExecError::Other(error::exponent(&right.to_string())));
-            }
-        },
+        "/" | "%" if right == 0.0 => return Err(ArithError::DivZero(right.to_string()).into()),
+        "/" => stack.push(ArithElem::Float(left / right)),
+        "%" => stack.push(ArithElem::Float(left % right)),
+        "**" => stack.push(check_result(left.powf(right), &format!("{}**{}", left, right))?),
         _ => return Err(ExecError::Other("not supported operator for float numbers".to_string())),
     }
 
@@ -67,10 +106,15 @@ pub fn substitute(
             true => return Err(ArithError::DivZero(right.to_string()).into()),
             false => cur / right,
         },
+        "%=" => match right == 0.0 {
+            true => return Err(ArithError::DivZero(right.to_string()).into()),
+            false => cur % right,
+        },
         _ => return Err(ArithError::OperandExpected(op.to_string()).into()),
     };
 
-    core.db.set_param2(&name, index, &new_value.to_string(), None)?;
+    let value_str = to_output(new_value, core)?;
+    core.db.set_param2(&name, index, &value_str, None)?;
     Ok(ArithElem::Float(new_value))
 }
 
@@ -84,3 +128,73 @@ pub fn parse(s: &str) -> Result<f64, ArithError> {
         (Err(_), _) => Err(ArithError::InvalidNumber(s.to_string())),
     }
 }
+
+fn to_f64(e: &ArithElem) -> f64 {
+    match e {
+        ArithElem::Integer(n) => *n as f64,
+        ArithElem::Float(f) => *f,
+        _ => 0.0,
+    }
+}
+
+fn check_result(f: f64, token: &str) -> Result<ArithElem, ExecError> {
+    match f.is_finite() {
+        true => Ok(ArithElem::Float(f)),
+        false => Err(ArithError::MathDomain(token.to_string()).into()),
+    }
+}
+
+pub fn call(name: &str, args: &mut [ArithmeticExpr], core: &mut ShellCore) -> Result<ArithElem, ExecError> {
+    let arg_texts: Vec<String> = args.iter().map(|a| a.text.clone()).collect();
+    let token = format!("{}({})", name, arg_texts.join(","));
+
+    let mut vals = vec![];
+    for a in args.iter_mut() {
+        vals.push(a.eval_elems(core, false)?);
+    }
+
+    let arity_ok = match name {
+        "min" | "max" => !vals.is_empty(),
+        _ => vals.len() == 1,
+    };
+    if !arity_ok {
+        return Err(ArithError::WrongArgCount(token).into());
+    }
+
+    let all_int = vals.iter().all(|v| matches!(v, ArithElem::Integer(_)));
+    let x = to_f64(&vals[0]);
+
+    match (name, &vals[0]) {
+        ("int", ArithElem::Integer(n)) => Ok(ArithElem::Integer(*n)),
+        ("int", _) => match x.is_finite() && x.abs() < i128::MAX as f64 {
+            true => Ok(ArithElem::Integer(x.trunc() as i128)),
+            false => Err(ArithError::Overflow(token).into()),
+        },
+        ("abs", ArithElem::Integer(n)) => match n.checked_abs() {
+            Some(a) => Ok(ArithElem::Integer(a)),
+            None => Err(ArithError::Overflow(token).into()),
+        },
+        ("ceil" | "floor" | "round", ArithElem::Integer(n)) => Ok(ArithElem::Integer(*n)),
+        ("min" | "max", _) if all_int => {
+            let ns = vals.iter().map(|v| if let ArithElem::Integer(n) = v { *n } else { 0 });
+            let ans = match name {
+                "min" => ns.min(),
+                _ => ns.max(),
+            };
+            Ok(ArithElem::Integer(ans.unwrap()))
+        },
+        ("min", _) => check_result(vals.iter().map(to_f64).fold(f64::INFINITY, f64::min), &token),
+        ("max", _) => check_result(vals.iter().map(to_f64).fold(f64::NEG_INFINITY, f64::max), &token),
+        ("abs", _) => check_result(x.abs(), &token),
+        ("ceil", _) => check_result(x.ceil(), &token),
+        ("floor", _) => check_result(x.floor(), &token),
+        ("round", _) => check_result(x.round(), &token),
+        ("sqrt", _) => check_result(x.sqrt(), &token),
+        ("exp", _) => check_result(x.exp(), &token),
+        ("log", _) => check_result(x.ln(), &token),
+        ("sin", _) => check_result(x.sin(), &token),
+        ("cos", _) => check_result(x.cos(), &token),
+        ("tan", _) => check_result(x.tan(), &token),
+        _ => Err(ArithError::OperandExpected(token).into()),
+    }
+}
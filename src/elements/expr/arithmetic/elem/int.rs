@@ -8,71 +8,172 @@ use crate::{
     utils::exit,
 };
 
-pub fn unary_calc(op: &str, num: i128, stack: &mut Vec<ArithElem>) -> Result<(), ExecError> {
-    match op {
-        "+" => stack.push(ArithElem::Integer(num)),
-        "-" => stack.push(ArithElem::Integer(-num)),
-        "!" => stack.push(ArithElem::Integer(if num == 0 { 1 } else { 0 })),
-        "~" => stack.push(ArithElem::Integer(!num)),
-        _ => exit::internal("unknown unary operator"),
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OverflowMode {
+    Wide,    // 128-bit integers with an error at the border (default)
+    Wrap,    // 64-bit integers wrapping around like Bash (shopt -s arith_wrap)
+    Checked, // 64-bit integers with an error at the border (shopt -s arith_checked)
+}
+
+impl OverflowMode {
+    pub fn get(core: &ShellCore) -> Self {
+        if core.shopts.query("arith_checked") {
+            OverflowMode::Checked
+        } else if core.shopts.query("arith_wrap") {
+            OverflowMode::Wrap
+        } else {
+            OverflowMode::Wide
+        }
+    }
+
+    fn bits(&self) -> i128 {
+        match self {
+            OverflowMode::Wide => 128,
+            _ => 64,
+        }
     }
+
+    pub fn fit(&self, n: i128, token: &str) -> Result<i128, ArithError> {
+        match self {
+            OverflowMode::Wide => Ok(n),
+            OverflowMode::Wrap => Ok(n as i64 as i128),
+            OverflowMode::Checked => match i64::try_from(n) {
+                Ok(_) => Ok(n),
+                Err(_) => Err(ArithError::Overflow(token.to_string())),
+            },
+        }
+    }
+}
+
+pub fn unary_calc(op: &str, num: i128, stack: &mut Vec<ArithElem>, mode: OverflowMode) -> Result<(), ExecError> {
+    let num = mode.fit(num, &num.to_string())?;
+    let token = op.to_string() + &num.to_string();
+
+    let ans = match op {
+        "+" => num,
+        "-" => match num.checked_neg() {
+            Some(n) => n,
+            None => return Err(ArithError::Overflow(token).into()),
+        },
+        "!" => if num == 0 { 1 } else { 0 },
+        "~" => !num,
+        _ => exit::internal("unknown unary operator"),
+    };
+    stack.push(ArithElem::Integer(mode.fit(ans, &token)?));
     Ok(())
 }
 
-pub fn bin_calc(op: &str, left: i128, right: i128, stack: &mut Vec<ArithElem>) -> Result<(), ArithError> {
+fn pow(base: i128, exp: i128, mode: OverflowMode) -> Option<i128> {
+    let mut ans: i128 = 1;
+    let mut base = base;
+    let mut exp = exp;
+
+    loop {
+        if exp & 1 == 1 {
+            ans = match mode {
+                OverflowMode::Wrap => (ans as i64).wrapping_mul(base as i64) as i128,
+                _ => ans.checked_mul(base)?,
+            };
+        }
+        exp >>= 1;
+        if exp == 0 {
+            return Some(ans);
+        }
+        base = match mode {
+            OverflowMode::Wrap => (base as i64).wrapping_mul(base as i64) as i128,
+            _ => base.checked_mul(base)?,
+        };
+    }
+}
+
+fn shift(op: &str, left: i128, right: i128, mode: OverflowMode) -> Option<i128> {
+    if right < 0 {
+        return Some(0);
+    }
+
+    let right = match mode {
+        OverflowMode::Wrap => right & 63,
+        _ => right,
+    };
+
+    if right >= mode.bits() {
+        return match op {
+            "<<" => None,
+            _ => Some(if left < 0 { -1 } else { 0 }),
+        };
+    }
+
+    match (op, mode) {
+        ("<<", OverflowMode::Wrap) => Some(((left as i64) << right) as i128),
+        ("<<", _) => {
+            // bits shifted out or into the sign bit are an overflow
+            let ans = left.checked_shl(right as u32)?;
+            match ans >> right == left {
+                true => Some(ans),
+                false => None,
+            }
+        },
+        _ => Some(left >> right),
+    }
+}
+
+pub fn calc(op: &str, left: i128, right: i128, mode: OverflowMode) -> Result<i128, ArithError> {
     let bool_to_01 = |b| {
         if b { 1 } else { 0 }
     };
 
+    let token = format!("{}{}{}", left, op, right);
+    let left = mode.fit(left, &token)?;
+    let right = mode.fit(right, &token)?;
+
     let ans = match op {
-        "+" => left + right,
-        "-" => left - right,
-        "*" => left * right,
-        "&" => left & right,
-        "^" => left ^ right,
-        "|" => left | right,
-        "&&" => bool_to_01(left != 0 && right != 0),
-        "||" => bool_to_01(left != 0 || right != 0),
-        "<<" => {
-            if right < 0 {
-                0
-            } else {
-                left << right
-            }
-        },
-        ">>" => {
-            if right < 0 {
-                0
-            } else {
-                left >> right
-            }
-        },
-        "<=" => bool_to_01(left <= right),
-        ">=" => bool_to_01(left >= right),
-        "<" => bool_to_01(left < right),
-        ">" => bool_to_01(left > right),
-        "==" => bool_to_01(left == right),
-        "!=" => bool_to_01(left != right),
+        "+" => left.checked_add(right),
+        "-" => left.checked_sub(right),
+        "*" => left.checked_mul(right),
+        "&" => Some(left & right),
+        "^" => Some(left ^ right),
+        "|" => Some(left | right),
+        "&&" => Some(bool_to_01(left != 0 && right != 0)),
+        "||" => Some(bool_to_01(left != 0 || right != 0)),
+        "<<" | ">>" => shift(op, left, right, mode),
+        "<=" => Some(bool_to_01(left <= right)),
+        ">=" => Some(bool_to_01(left >= right)),
+        "<" => Some(bool_to_01(left < right)),
+        ">" => Some(bool_to_01(left > right)),
+        "==" => Some(bool_to_01(left == right)),
+        "!=" => Some(bool_to_01(left != right)),
         "%" | "/" => {
             if right == 0 {
                 return Err(ArithError::DivZero(right.to_string()));
             }
             match op {
-                "%" => left % right,
-                _ => left / right,
+                "%" => left.checked_rem(right),
+                _ => left.checked_div(right),
             }
         },
         "**" => {
-            if right >= 0 {
-                let r = right.try_into().unwrap();
-                left.pow(r)
-            } else {
+            if right < 0 {
                 return Err(ArithError::Exponent(right));
             }
+            pow(left, right, mode)
         },
         _ => exit::internal("unknown binary operator"),
     };
 
+    match ans {
+        Some(n) => mode.fit(n, &token),
+        None => Err(ArithError::Overflow(token)),
+    }
+}
+
+pub fn bin_calc(
+    op: &str,
+    left: i128,
+    right: i128,
+    stack: &mut Vec<ArithElem>,
+    mode: OverflowMode,
+) -> Result<(), ArithError> {
+    let ans = calc(op, left, right, mode)?;
     stack.push(ArithElem::Integer(ans));
     Ok(())
 }
@@ -86,34 +187,8 @@ pub fn substitute(
     core: &mut ShellCore,
 ) -> Result<ArithElem, ExecError> {
     let new_value = match op {
-        "+=" => cur + right,
-        "-=" => cur - right,
-        "*=" => cur * right,
-        "&=" => cur & right,
-        "^=" => cur ^ right,
-        "|=" => cur | right,
-        "<<=" => {
-            if right < 0 {
-                0
-            } else {
-                cur << right
-            }
-        },
-        ">>=" => {
-            if right < 0 {
-                0
-            } else {
-                cur >> right
-            }
-        },
-        "/=" | "%=" => {
-            if right == 0 {
-                return Err(ArithError::DivZero(right.to_string()).into());
-            }
-            match op == "%=" {
-                true => cur % right,
-                false => cur / right,
-            }
+        "+=" | "-=" | "*=" | "&=" | "^=" | "|=" | "<<=" | ">>=" | "/=" | "%=" => {
+            calc(&op[..op.len() - 1], cur, right, OverflowMode::get(core))?
         },
         _ => return Err(ArithError::OperandExpected(op.to_string()).into()),
     };
@@ -127,9 +202,8 @@ fn parse_with_base(base: i128, s: &mut String, org: &str) -> Result<i128, ArithE
         return Err(ArithError::InvalidIntConst(org.to_string()));
     }
 
-    let mut ans = 0;
+    let mut ans: i128 = 0;
     for ch in s.chars() {
-        ans *= base;
         let num = if ch >= '0' && ch <= '9' {
             ch as i128 - '0' as i128
         } else if ch >= 'a' && ch <= 'z' {
@@ -147,10 +221,14 @@ fn parse_with_base(base: i128, s: &mut String, org: &str) -> Result<i128, ArithE
             return Err(ArithError::InvalidNumber(org.to_string()));
         };
 
-        match num < base {
-            true => ans += num,
-            false => return Err(ArithError::ValueTooGreatForBase(org.to_string())),
+        if num >= base {
+            return Err(ArithError::ValueTooGreatForBase(org.to_string()));
         }
+
+        ans = match ans.checked_mul(base).and_then(|n| n.checked_add(num)) {
+            Some(n) => n,
+            None => return Err(ArithError::Overflow(org.to_string())),
+        };
     }
 
     Ok(ans)
@@ -165,8 +243,11 @@ fn get_base(s: &mut String) -> Result<i128, ArithError> {
     }
 
     if s.starts_with("0") && s.len() > 1 {
-        if s.contains('#') {
-            return Err(ArithError::InvalidNumber(s.clone()));
+        if let Some(n) = s.find('#') {
+            return match s[..n].contains(['8', '9']) {
+                true => Err(ArithError::ValueTooGreatForBase(s.clone())),
+                false => Err(ArithError::InvalidNumber(s.clone())),
+            };
         }
         s.remove(0);
         return Ok(8);
@@ -176,7 +257,7 @@ fn get_base(s: &mut String) -> Result<i128, ArithError> {
         let base_str = s[..n].to_string();
         *s = s[(n + 1)..].to_string();
         return match base_str.parse::<i128>() {
-            Ok(n) => match n <= 64 {
+            Ok(n) => match (2..=64).contains(&n) {
                 true => Ok(n),
                 false => Err(ArithError::InvalidBase(s_org.to_string())),
             },
@@ -197,6 +278,9 @@ pub fn parse(s: &str) -> Result<i128, ArithError> {
 
     let mut sw = s.to_string();
     let sign = variable::get_sign(&mut sw);
+    if sw == "0x" || sw == "0X" {
+        return Ok(0); // as Bash does
+    }
     let base = get_base(&mut sw)?;
     let n = parse_with_base(base, &mut sw, s)?;
 
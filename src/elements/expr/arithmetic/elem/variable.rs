@@ -1,7 +1,7 @@
 // SPDX-FileCopyrightText: 2024 Ryuichi Ueda ryuichiueda@gmail.com
 // SPDX-License-Identifier: BSD-3-Clause
 
-use super::{super::ArithElem, float, int};
+use super::{super::ArithElem, float, int, int::OverflowMode};
 use crate::{
     Feeder, ShellCore,
     elements::expr::arithmetic::ArithmeticExpr,
@@ -21,14 +21,17 @@ fn to_num(w: &str, sub: &String, core: &mut ShellCore) -> Result<ArithElem, Exec
 
 pub fn str_to_num(name: &str, sub: &String, core: &mut ShellCore) -> Result<ArithElem, ExecError> {
     let mut name = name.to_string();
+    let mut float_var = false;
 
     const RESOLVE_LIMIT: i32 = 100; //000;
 
     for i in 0..RESOLVE_LIMIT {
         if utils::is_name(&name, core) {
             if i == RESOLVE_LIMIT - 1 {
-                return Err(ArithError::Recursion(name.clone()).into());
+                let err = ArithError::Recursion(name.clone());
+                return Err(ExecError::ArithError(name, err));
             }
+            float_var |= core.db.has_flag(&name, 'E');
             name = core.db.get_elem_or_param(&name, sub)?;
             continue;
         }
@@ -36,20 +39,40 @@ pub fn str_to_num(name: &str, sub: &String, core: &mut ShellCore) -> Result<Arit
     }
     // name is not a name here
 
+    if name.contains('.') && !float::enabled(core) {
+        return Err(float::disabled_error(name.trim()));
+    }
+
     match try_parse_to_num(&name) {
+        Ok(ArithElem::Integer(n)) if float_var => Ok(ArithElem::Float(n as f64)),
         Ok(e) => Ok(e),
         Err(_) => resolve_arithmetic_op(&name, core),
     }
 }
 
 fn resolve_arithmetic_op(name: &str, core: &mut ShellCore) -> Result<ArithElem, ExecError> {
+    const RECURSION_LIMIT: i32 = 256; // smaller than Bash (1024) to keep the stack of debug builds
+
+    if core.arith_level >= RECURSION_LIMIT {
+        let err = ArithError::Recursion(name.to_string());
+        return Err(ExecError::ArithError(name.to_string(), err));
+    }
+
     let mut f = Feeder::new(&name);
     let mut parsed = match ArithmeticExpr::parse_after_eval(&mut f, core, "") {
         Ok(Some(p)) => p,
+        Err(e) => return Err(e),
         _ => return Err(ArithError::OperandExpected(name.to_string()).into()),
     };
 
-    parsed.eval_elems(core, true)
+    core.arith_level += 1;
+    let ans = parsed.eval_elems(core, true);
+    core.arith_level -= 1;
+
+    match ans {
+        Err(ExecError::ArithError(s, e)) if s.is_empty() => Err(ExecError::ArithError(name.trim().to_string(), e)),
+        ans => ans,
+    }
 }
 
 fn try_parse_to_num(name: &str) -> Result<ArithElem, ExecError> {
@@ -71,17 +94,19 @@ pub fn set_and_to_value(
 ) -> Result<ArithElem, ExecError> {
     match str_to_num(&name, sub, core) {
         Ok(ArithElem::Integer(n)) => {
+            let new_value = int::calc("+", n, inc, OverflowMode::get(core))?;
             if inc != 0 {
-                core.db.set_param2(&name, sub, &(n + inc).to_string(), None)?;
+                core.db.set_param2(&name, sub, &new_value.to_string(), None)?;
             }
             match pre {
-                true => Ok(ArithElem::Integer(n + inc)),
+                true => Ok(ArithElem::Integer(new_value)),
                 false => Ok(ArithElem::Integer(n)),
             }
         },
         Ok(ArithElem::Float(n)) => {
             if inc != 0 {
-                core.db.set_param2(&name, sub, &(n + inc as f64).to_string(), None)?;
+                let new_value = float::to_output(n + inc as f64, core)?;
+                core.db.set_param2(&name, sub, &new_value, None)?;
             }
             match pre {
                 true => Ok(ArithElem::Float(n + inc as f64)),
@@ -109,6 +134,9 @@ pub fn substitution(op: &str, stack: &mut Vec<ArithElem>, core: &mut ShellCore)
     let mut right = match stack.pop() {
         Some(mut e) => {
             e.change_to_value(0, core)?;
+            if let ArithElem::Integer(n) = e {
+                e = ArithElem::Integer(OverflowMode::get(core).fit(n, &n.to_string())?);
+            }
             e
         },
         _ => return Err(ArithError::OperandExpected(op.to_string()).into()),
@@ -138,7 +166,10 @@ fn subs(
     }
 
     let name = w.to_string();
-    let right_str = right_value.to_string();
+    let right_str = match &*right_value {
+        ArithElem::Float(f) => float::to_output(*f, core)?,
+        e => e.to_string(),
+    };
 
     match op {
         "=" => {
@@ -153,14 +184,16 @@ fn subs(
             if let Ok(left) = val_str.parse::<i128>() {
                 match right_value {
                     ArithElem::Integer(n) => {
-                        core.db.set_param2(&name, sub, &(left + *n).to_string(), None)?;
-                        return Ok(ArithElem::Integer(left + *n));
+                        let new_value = int::calc("+", left, *n, OverflowMode::get(core))?;
+                        core.db.set_param2(&name, sub, &new_value.to_string(), None)?;
+                        return Ok(ArithElem::Integer(new_value));
                     },
                     _ => {},
                 }
             } else if let Ok(left) = val_str.parse::<f64>() {
                 if let ArithElem::Float(f) = right_value {
-                    core.db.set_param2(&name, sub, &(left + *f).to_string(), None)?;
+                    let new_value = float::to_output(left + *f, core)?;
+                    core.db.set_param2(&name, sub, &new_value, None)?;
                     return Ok(ArithElem::Float(left + *f));
                 }
             }
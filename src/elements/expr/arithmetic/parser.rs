@@ -71,6 +71,10 @@ impl ArithmeticExpr {
         let w = feeder.consume(len);
         ans.text += &w.clone();
 
+        if w.contains('.') && !float::enabled(core) {
+            return Err(float::disabled_error(&w));
+        }
+
         if !w.contains('.') {
             match int::parse(&w) {
                 Ok(n) => {
@@ -228,6 +232,43 @@ impl ArithmeticExpr {
         return Ok(true);
     }
 
+    fn eat_function(feeder: &mut Feeder, core: &mut ShellCore, ans: &mut Self) -> Result<bool, ExecError> {
+        let len = feeder.scanner_name(core);
+        if len == 0 || !float::enabled(core) || feeder.len() <= len {
+            return Ok(false);
+        }
+
+        let call = feeder.refer(len + 1).to_string();
+        if !call.ends_with("(") || !float::FUNCTIONS.contains(&&call[..len]) {
+            return Ok(false);
+        }
+
+        let name = feeder.consume(len);
+        ans.text += &name.clone();
+        ans.text += &feeder.consume(1);
+        let arith = Self::parse_after_eval(feeder, core, "(")?;
+        if arith.is_none() || !feeder.starts_with(")") {
+            return Ok(false);
+        }
+
+        let arith = arith.unwrap();
+        ans.text += &arith.text;
+        ans.text += &feeder.consume(1);
+
+        let mut args = vec![];
+        if !arith.elements.is_empty() {
+            for elems in arith.elements.split(|e| matches!(e, ArithElem::BinaryOp(op) if op == ",")) {
+                let mut a = ArithmeticExpr::new();
+                a.text = elems.iter().map(|e| e.to_string()).collect();
+                a.elements = elems.to_vec();
+                args.push(a);
+            }
+        }
+
+        ans.elements.push(ArithElem::Function(name, args));
+        Ok(true)
+    }
+
     fn eat_paren(feeder: &mut Feeder, core: &mut ShellCore, ans: &mut Self) -> Result<bool, ParseError> {
         if !feeder.starts_with("(") {
             return Ok(false);
@@ -287,6 +328,7 @@ impl ArithmeticExpr {
                 || Self::eat_unary_operator(feeder, &mut ans, core)
                 || Self::eat_paren_internal(feeder, core, &mut ans)?
                 || Self::eat_binary_operator(feeder, &mut ans, core)
+                || Self::eat_function(feeder, core, &mut ans)?
                 || Self::eat_array_elem(feeder, &mut ans, core, true)?
                 || Self::eat_num(feeder, &mut ans, core)?
                 || Self::eat_word(feeder, &mut ans, core, true)
@@ -38,8 +38,11 @@ fn rev_polish_op(elem: &ArithElem, stack: &mut Vec<ArithElem>, ans: &mut Vec<Ari
             },
             Some(_) => {
                 let last = stack.last().unwrap();
-                if last.order() < elem.order() || (last.order() == 2 && elem.order() == 2) {
-                    // assignment
+                if last.order() < elem.order()
+                    || (last.order() == 2 && elem.order() == 2) // assignment
+                    || (last.order() == 17 && elem.order() == 17)
+                {
+                    // right associative operators
                     stack.push(elem.clone());
                     break;
                 }
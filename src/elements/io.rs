@@ -1,12 +1,32 @@
 // SPDX-FileCopyrightText: 2022 Ryuichi Ueda ryuichiueda@gmail.com
 // SPDX-License-Identifier: BSD-3-Clause
 
+pub mod dev;
 pub mod pipe;
 pub mod redirect;
 
-use std::os::unix::prelude::RawFd;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    fs::File,
+    io::Error,
+    os::unix::prelude::RawFd,
+};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::ffi::CString;
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+use std::{env, fs::OpenOptions, io::ErrorKind, os::unix::fs::OpenOptionsExt, process};
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use nix::sys::memfd::{self, MemFdCreateFlag};
 
-use nix::{errno::Errno, fcntl, unistd};
+use nix::{
+    errno::Errno,
+    fcntl,
+    fcntl::FdFlag,
+    sys::resource::{Resource, getrlimit},
+    unistd,
+};
 
 use crate::{
     ShellCore,
@@ -24,6 +44,9 @@ pub fn replace(from: RawFd, to: RawFd) -> bool {
     if from < 0 || to < 0 {
         return false;
     }
+    if from == to {
+        return true;
+    }
 
     match unistd::dup2(from, to) {
         Ok(_) => {
@@ -53,6 +76,145 @@ fn share(from: RawFd, to: RawFd) -> Result<(), ExecError> {
     }
 }
 
+#[derive(Debug, Default)]
+pub struct FdTable {
+    user:         BTreeSet<RawFd>,
+    internal:     BTreeMap<RawFd, String>,
+    pub proc_sub: Vec<RawFd>,
+}
+
+impl FdTable {
+    pub fn set_internal(&mut self, fd: RawFd, name: &str) {
+        self.internal.insert(fd, name.to_string());
+    }
+
+    pub fn remove_internal(&mut self, fd: RawFd) {
+        self.internal.remove(&fd);
+    }
+
+    // called after exec without a command changes fds
+
+    pub fn update_user(&mut self, fd: RawFd) {
+        if fd < 0 {
+            return;
+        }
+        match is_open(fd) {
+            true => self.user.insert(fd),
+            false => self.user.remove(&fd),
+        };
+    }
+
+    pub fn list(&self) -> Vec<String> {
+        let mut ans = vec![];
+        for fd in open_fds() {
+            let kind = match fd {
+                _ if self.user.contains(&fd) => "user".to_string(),
+                _ if self.proc_sub.contains(&fd) => "process substitution".to_string(),
+                _ if self.internal.contains_key(&fd) => format!("internal ({})", &self.internal[&fd]),
+                0..=2 => "standard".to_string(),
+                _ if is_cloexec(fd) => "internal".to_string(),
+                _ => "inherited".to_string(),
+            };
+
+            let target = fs::read_link(format!("/dev/fd/{}", fd)).map(|p| p.display().to_string()).unwrap_or_default();
+            ans.push(format!("{}\t{}\t{}", fd, kind, target).trim_end().to_string());
+        }
+        ans
+    }
+}
+
+pub fn is_open(fd: RawFd) -> bool {
+    fcntl::fcntl(fd, fcntl::F_GETFD).is_ok()
+}
+
+fn is_cloexec(fd: RawFd) -> bool {
+    match fcntl::fcntl(fd, fcntl::F_GETFD) {
+        Ok(flags) => FdFlag::from_bits_truncate(flags).contains(FdFlag::FD_CLOEXEC),
+        Err(_) => false,
+    }
+}
+
+pub fn open_fds() -> Vec<RawFd> {
+    let mut ans: Vec<RawFd> = match fs::read_dir("/dev/fd") {
+        Ok(dir) => dir.filter_map(|e| e.ok()?.file_name().to_str()?.parse().ok()).collect(),
+        Err(_) => {
+            let max = match getrlimit(Resource::RLIMIT_NOFILE) {
+                Ok((soft, _)) => soft.min(1024) as RawFd,
+                Err(_) => 1024,
+            };
+            (0..max).collect()
+        },
+    };
+
+    ans.retain(|fd| is_open(*fd));
+    ans.sort();
+    ans
+}
+
+// moves an fd used by the shell itself out of 0-9, which are for users
+pub fn move_out_of_user_range(fd: RawFd, cloexec: bool) -> RawFd {
+    if fd >= 10 {
+        if cloexec {
+            let _ = fcntl::fcntl(fd, fcntl::F_SETFD(FdFlag::FD_CLOEXEC));
+        }
+        return fd;
+    }
+
+    let arg = match cloexec {
+        true => fcntl::F_DUPFD_CLOEXEC(10),
+        false => fcntl::F_DUPFD(10),
+    };
+    match fcntl::fcntl(fd, arg) {
+        Ok(new_fd) => {
+            close(fd, "sush(fatal): cannot move an fd");
+            new_fd
+        },
+        Err(_) => fd,
+    }
+}
+
+// fds of <() and >() are kept open until the consuming command ends
+pub fn close_proc_sub_fds(core: &mut ShellCore, from: usize) {
+    for fd in core.fds.proc_sub.split_off(from) {
+        close(fd, "sush(fatal): cannot close process substitution");
+    }
+}
+
+// an anonymous in-memory file, which no other process can open by a path
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn anonymous_file(tag: &str) -> Result<File, Error> {
+    let name = CString::new(format!("sush-{}", tag)).unwrap_or_default();
+    match memfd::memfd_create(&name, MemFdCreateFlag::MFD_CLOEXEC) {
+        Ok(fd) => Ok(File::from(fd)),
+        Err(e) => Err(Error::from(e)),
+    }
+}
+
+// an unlinked temporary file only readable by the user
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub fn anonymous_file(tag: &str) -> Result<File, Error> {
+    let dir = env::temp_dir();
+    loop {
+        let path = dir.join(format!("sush-{}-{}-{:016x}", tag, process::id(), rand::random::<u64>()));
+        match OpenOptions::new().read(true).write(true).create_new(true).mode(0o600).open(&path) {
+            Ok(f) => {
+                let _ = fs::remove_file(&path);
+                return Ok(f);
+            },
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {},
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+pub fn free_fd(from: RawFd) -> RawFd {
+    let mut fd = from;
+    while is_open(fd) {
+        fd += 1;
+    }
+    fd
+}
+
 pub fn backup(from: RawFd) -> RawFd {
     if fcntl::fcntl(from, fcntl::F_GETFD).is_err() {
         return from;
@@ -0,0 +1,74 @@
+// SPDX-FileCopyrightText: 2025 Ryuichi Ueda ryuichiueda@gmail.com
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{Error, ErrorKind},
+    net::{SocketAddr, TcpStream, ToSocketAddrs, UdpSocket},
+    os::fd::{FromRawFd, IntoRawFd, RawFd},
+};
+
+use nix::unistd;
+
+use crate::utils::{c_string, file_check};
+
+pub fn open(path: &str, options: &OpenOptions) -> Result<File, Error> {
+    if let Some((host, port)) = path.strip_prefix("/dev/tcp/").and_then(split_host_port) {
+        let stream = TcpStream::connect(to_addrs(host, port)?.as_slice())?;
+        return Ok(to_file(stream.into_raw_fd()));
+    }
+    if let Some((host, port)) = path.strip_prefix("/dev/udp/").and_then(split_host_port) {
+        return open_udp(host, port);
+    }
+
+    if !file_check::exists(path) {
+        if let Some(fd) = standard_fd(path) {
+            return Ok(to_file(unistd::dup(fd)?));
+        }
+    }
+
+    options.open(c_string::to_os_string(path))
+}
+
+fn standard_fd(path: &str) -> Option<RawFd> {
+    match path {
+        "/dev/stdin" => Some(0),
+        "/dev/stdout" => Some(1),
+        "/dev/stderr" => Some(2),
+        _ => path.strip_prefix("/dev/fd/")?.parse::<RawFd>().ok(),
+    }
+}
+
+fn split_host_port(host_port: &str) -> Option<(&str, u16)> {
+    let (host, port) = host_port.rsplit_once('/')?;
+    if host.is_empty() || host.contains('/') {
+        return None;
+    }
+    Some((host, port.parse::<u16>().ok()?))
+}
+
+fn to_addrs(host: &str, port: u16) -> Result<Vec<SocketAddr>, Error> {
+    match (host.trim_start_matches('[').trim_end_matches(']'), port).to_socket_addrs() {
+        Ok(addrs) => Ok(addrs.collect()),
+        Err(_) => Err(Error::from(ErrorKind::AddrNotAvailable)),
+    }
+}
+
+fn open_udp(host: &str, port: u16) -> Result<File, Error> {
+    let mut last_err = Error::from(ErrorKind::AddrNotAvailable);
+    for addr in to_addrs(host, port)? {
+        let local = match addr {
+            SocketAddr::V4(_) => "0.0.0.0:0",
+            SocketAddr::V6(_) => "[::]:0",
+        };
+        match UdpSocket::bind(local).and_then(|s| s.connect(addr).map(|_| s)) {
+            Ok(s) => return Ok(to_file(s.into_raw_fd())),
+            Err(e) => last_err = e,
+        }
+    }
+    Err(last_err)
+}
+
+fn to_file(fd: RawFd) -> File {
+    unsafe { File::from_raw_fd(fd) }
+}
@@ -66,6 +66,13 @@ impl Pipe {
         self.pgid = pgid;
     }
 
+    pub fn set_reverse(&mut self, pgid: Pid) {
+        let (recv, send) = unistd::pipe().expect("Cannot open pipe");
+        self.recv = send.into_raw_fd();
+        self.prev = recv.into_raw_fd();
+        self.pgid = pgid;
+    }
+
     pub fn connect(&mut self) -> Result<(), ExecError> {
         io::close(self.recv, "Cannot close in-pipe");
         io::replace(self.send, 1);
@@ -3,24 +3,27 @@
 
 use std::{
     fs::{File, OpenOptions},
-    io::{Error, Write},
-    os::fd::{FromRawFd, IntoRawFd, RawFd},
-    process,
+    io::{Error, Seek, Write},
+    os::fd::{IntoRawFd, RawFd},
 };
 
-use nix::{unistd, unistd::ForkResult};
+use nix::{fcntl, unistd};
 
 use crate::{
     Feeder, ShellCore,
     elements::{
-        io, subword,
+        io,
+        io::dev,
+        subword,
         subword::filler::FillerSubword,
         word::{Word, WordMode},
     },
-    error::{exec::ExecError, parse::ParseError},
-    utils::{exit, file_check},
+    error::{exec::ExecError, input::InputError, parse::ParseError},
+    utils::{c_string, exit, file_check},
 };
 
+const HERE_PIPE_SIZE: usize = 4096;
+
 #[derive(Debug, Clone, Default)]
 pub struct Redirect {
     pub text:              String,
@@ -30,12 +33,16 @@ pub struct Redirect {
     left_fd:               RawFd,
     left_backup:           RawFd,
     extra_left_backup:     RawFd, // &>, &>>用
+    moved_fd:              RawFd, // n>&m-, n<&m-用
+    moved_fd_backup:       RawFd,
     here_data:             Word,
     pub called_as_heredoc: bool,
 }
 
 impl Redirect {
     pub fn connect(&mut self, restore: bool, core: &mut ShellCore) -> Result<(), ExecError> {
+        self.moved_fd = -1;
+
         if self.symbol == "<<" || self.symbol == "<<-" {
             return self.redirect_heredocument(core, restore);
         }
@@ -50,7 +57,7 @@ impl Redirect {
 
         if core.db.flags.contains('r') {
             match self.symbol.as_str() {
-                ">" | ">|" | "<>" | ">&" | "&>" | ">>" => {
+                ">" | ">|" | "<>" | ">&" | "&>" | ">>" | "&>>" => {
                     let msg = format!("{}: restricted: cannot redirect output", &args[0]);
                     return Err(ExecError::Other(msg));
                 },
@@ -60,25 +67,65 @@ impl Redirect {
 
         self.right.text = args[0].clone();
 
+        let mut restore = restore;
+        if let Some(name) = self.fd_var_name() {
+            self.set_left_fd_from_var(&name, core)?;
+            restore = restore && core.shopts.query("varredir_close");
+        }
+
+        if self.symbol == ">&" && self.left.is_empty() && !self.is_fd_operand() {
+            self.symbol = "&>".to_string(); // >&file is the same as &>file
+        }
+
         if core.options.query("noclobber")
-            && (self.symbol.as_str() == ">" || self.symbol.as_str() == ">>")
-            && file_check::exists(&self.right.text)
+            && (self.symbol == ">" || self.symbol == "&>")
+            && file_check::is_regular_file(&self.right.text)
         {
             return Err(ExecError::CannotOverwriteExistingFile(self.right.text.clone()));
         }
 
         match self.symbol.as_str() {
-            "<" => self.redirect_simple_input(restore),  // <
-            ">" => self.redirect_simple_output(restore), // >
-            ">&" => self.redirect_output_fd(restore),    // >&2
-            "<&" => self.redirect_input_fd(restore),     // <&2
+            "<" => self.redirect_simple_input(restore),         // <
+            ">" | ">|" => self.redirect_simple_output(restore), // >, >|
+            ">&" => self.redirect_output_fd(restore),           // >&2, >&2-, >&-
+            "<&" => self.redirect_input_fd(restore),            // <&2, <&2-, <&-
             ">>" => self.redirect_append(restore),
+            "<>" => self.redirect_read_write(restore),
             "&>" => self.redirect_both_output(restore),
+            "&>>" => self.redirect_both_append(restore),
             _ => exit::internal(" (Unknown redirect symbol)"),
         }
     }
 
+    fn is_fd_operand(&self) -> bool {
+        let text = self.right.text.strip_suffix("-").unwrap_or(&self.right.text);
+        text.is_empty() || text.parse::<RawFd>().is_ok()
+    }
+
+    fn fd_var_name(&self) -> Option<String> {
+        let name = self.left.strip_prefix("{")?.strip_suffix("}")?;
+        Some(name.to_string())
+    }
+
+    fn set_left_fd_from_var(&mut self, name: &str, core: &mut ShellCore) -> Result<(), ExecError> {
+        if self.right.text == "-" {
+            let value = core.db.get_param(name).unwrap_or_default();
+            self.left_fd = match value.parse::<RawFd>() {
+                Ok(n) if n >= 0 => n,
+                _ => return Err(ExecError::AmbiguousRedirect(name.to_string())),
+            };
+            return Ok(());
+        }
+
+        self.left_fd = io::free_fd(10);
+        core.db.set_param(name, &self.left_fd.to_string(), None)
+    }
+
     fn set_left_fd(&mut self, default_fd: RawFd) {
+        if self.fd_var_name().is_some() {
+            return;
+        }
+
         self.left_fd = match self.left.len() {
             0 => default_fd,
             _ => self.left.parse().unwrap(),
@@ -86,13 +133,16 @@ impl Redirect {
     }
 
     fn connect_to_file(&mut self, file_open_result: Result<File, Error>, restore: bool) -> Result<(), ExecError> {
-        if restore {
-            self.left_backup = io::backup(self.left_fd);
-        }
-
         match file_open_result {
             Ok(file) => {
                 let fd = file.into_raw_fd();
+                if restore {
+                    // the file took the number of the closed left fd, so restore() closes it
+                    self.left_backup = match fd == self.left_fd {
+                        true => self.left_fd,
+                        false => io::backup(self.left_fd),
+                    };
+                }
                 let result = io::replace(fd, self.left_fd);
                 if !result {
                     io::close(fd, &format!("sush(fatal): file does not close"));
@@ -102,71 +152,105 @@ impl Redirect {
                 }
                 Ok(())
             },
-            _ => {
-                let msg = format!("{}: {}", &self.right.text, Error::last_os_error().kind());
+            Err(e) => {
+                let msg = format!("{}: {}", &self.right.text, e.kind());
                 Err(ExecError::Other(msg))
             },
         }
     }
 
+    fn create_options() -> OpenOptions {
+        let mut options = OpenOptions::new();
+        options.create(true).write(true).truncate(true);
+        options
+    }
+
     fn redirect_simple_input(&mut self, restore: bool) -> Result<(), ExecError> {
         self.set_left_fd(0);
-        self.connect_to_file(File::open(&self.right.text), restore)
+        self.connect_to_file(dev::open(&self.right.text, OpenOptions::new().read(true)), restore)
     }
 
     fn redirect_simple_output(&mut self, restore: bool) -> Result<(), ExecError> {
         self.set_left_fd(1);
-        self.connect_to_file(File::create(&self.right.text), restore)
+        self.connect_to_file(dev::open(&self.right.text, &Self::create_options()), restore)
     }
 
     fn redirect_output_fd(&mut self, restore: bool) -> Result<(), ExecError> {
+        self.set_left_fd(1);
+        self.duplicate_fd(restore)
+    }
+
+    fn redirect_input_fd(&mut self, restore: bool) -> Result<(), ExecError> {
+        self.set_left_fd(0);
+        self.duplicate_fd(restore)
+    }
+
+    fn duplicate_fd(&mut self, restore: bool) -> Result<(), ExecError> {
         if self.right.text == "-" {
-            self.set_left_fd(1);
-            io::close(self.left_fd, "cannot close");
-            return Ok(());
+            return self.close_left_fd(restore);
         }
 
-        let right_fd = match self.right.text.parse::<RawFd>() {
+        let (text, move_fd) = match self.right.text.strip_suffix("-") {
+            Some(t) => (t.to_string(), true),
+            None => (self.right.text.clone(), false),
+        };
+
+        let right_fd = match text.parse::<RawFd>() {
             Ok(n) => n,
             _ => return Err(ExecError::AmbiguousRedirect(self.right.text.clone())),
         };
-        self.set_left_fd(1);
+
+        if fcntl::fcntl(right_fd, fcntl::F_GETFD).is_err() {
+            return Err(ExecError::BadFd(right_fd));
+        }
+        if right_fd == self.left_fd {
+            return Ok(());
+        }
 
         if restore {
             self.left_backup = io::backup(self.left_fd);
         }
+        io::share(right_fd, self.left_fd)?;
 
-        io::share(right_fd, self.left_fd)
+        if move_fd {
+            self.moved_fd = right_fd;
+            if restore {
+                self.moved_fd_backup = io::backup(right_fd);
+            }
+            io::close(right_fd, "cannot close");
+        }
+        Ok(())
     }
 
-    fn redirect_input_fd(&mut self, restore: bool) -> Result<(), ExecError> {
-        if self.right.text == "-" {
-            self.set_left_fd(0);
-            io::close(self.left_fd, "cannot close");
+    fn close_left_fd(&mut self, restore: bool) -> Result<(), ExecError> {
+        if fcntl::fcntl(self.left_fd, fcntl::F_GETFD).is_err() {
+            self.left_fd = -1;
             return Ok(());
         }
 
-        let right_fd = match self.right.text.parse::<RawFd>() {
-            Ok(n) => n,
-            _ => return Err(ExecError::AmbiguousRedirect(self.right.text.clone())),
-        };
-        self.set_left_fd(0);
-
         if restore {
             self.left_backup = io::backup(self.left_fd);
         }
-
-        io::share(right_fd, self.left_fd)
+        io::close(self.left_fd, "cannot close");
+        Ok(())
     }
 
     fn redirect_append(&mut self, restore: bool) -> Result<(), ExecError> {
         self.set_left_fd(1);
-        self.connect_to_file(OpenOptions::new().create(true).write(true).append(true).open(&self.right.text), restore)
+        self.connect_to_file(dev::open(&self.right.text, OpenOptions::new().create(true).append(true)), restore)
+    }
+
+    fn redirect_read_write(&mut self, restore: bool) -> Result<(), ExecError> {
+        self.set_left_fd(0);
+        self.connect_to_file(
+            dev::open(&self.right.text, OpenOptions::new().create(true).read(true).write(true).truncate(false)),
+            restore,
+        )
     }
 
     fn redirect_both_output(&mut self, restore: bool) -> Result<(), ExecError> {
         self.left_fd = 1;
-        self.connect_to_file(File::create(&self.right.text), restore)?;
+        self.connect_to_file(dev::open(&self.right.text, &Self::create_options()), restore)?;
 
         if restore {
             self.extra_left_backup = io::backup(2);
@@ -174,16 +258,17 @@ impl Redirect {
         io::share(1, 2)
     }
 
-    fn redirect_heredocument(&mut self, core: &mut ShellCore, restore: bool) -> Result<(), ExecError> {
-        self.left_fd = 0;
-        let (r, s) = unistd::pipe().expect("Cannot open pipe");
-        let recv = r.into_raw_fd();
-        let send = s.into_raw_fd();
+    fn redirect_both_append(&mut self, restore: bool) -> Result<(), ExecError> {
+        self.left_fd = 1;
+        self.connect_to_file(dev::open(&self.right.text, OpenOptions::new().create(true).append(true)), restore)?;
 
         if restore {
-            self.left_backup = io::backup(0);
+            self.extra_left_backup = io::backup(2);
         }
+        io::share(1, 2)
+    }
 
+    fn redirect_heredocument(&mut self, core: &mut ShellCore, restore: bool) -> Result<(), ExecError> {
         let right = self.right.make_unquoted_word().unwrap_or("".to_string());
         let quoted = right != self.right.text;
 
@@ -192,50 +277,58 @@ impl Redirect {
             true => self.here_data.text.clone(),
         };
 
-        match unsafe { unistd::fork()? } {
-            ForkResult::Child => {
-                io::close(recv, "here_data close error (child recv)");
-                let mut f = unsafe { File::from_raw_fd(send) };
-                let _ = write!(&mut f, "{}", &text);
-                f.flush().unwrap();
-                io::close(send, "here_data close error (child send)");
-                process::exit(0);
-            },
-            ForkResult::Parent { child: _ } => {
-                io::close(send, "here_data close error (parent send)");
-                io::replace(recv, 0);
-            },
-        }
-        Ok(())
+        self.connect_here_data(&text, core, restore)
     }
 
     fn redirect_herestring(&mut self, core: &mut ShellCore, restore: bool) -> Result<(), ExecError> {
-        self.left_fd = 0;
-        let (r, s) = unistd::pipe().expect("Cannot open pipe");
-        let recv = r.into_raw_fd();
-        let send = s.into_raw_fd();
+        let text = self.right.eval_as_herestring(core)? + "\n";
+        self.connect_here_data(&text, core, restore)
+    }
+
+    fn connect_here_data(&mut self, text: &str, core: &mut ShellCore, restore: bool) -> Result<(), ExecError> {
+        let mut restore = restore;
+        match self.fd_var_name() {
+            Some(name) => {
+                self.left_fd = io::free_fd(10);
+                core.db.set_param(&name, &self.left_fd.to_string(), None)?;
+                restore = restore && core.shopts.query("varredir_close");
+            },
+            None => self.set_left_fd(0),
+        }
 
         if restore {
-            self.left_backup = io::backup(0);
+            self.left_backup = io::backup(self.left_fd);
         }
 
-        let text = self.right.eval_as_herestring(core)?;
+        let fd = Self::here_data_fd(c_string::to_carg(&text.to_string()).as_bytes())?;
+        io::replace(fd, self.left_fd);
+        Ok(())
+    }
 
-        match unsafe { unistd::fork()? } {
-            ForkResult::Child => {
-                io::close(recv, "here_data close error (child recv)");
-                let mut f = unsafe { File::from_raw_fd(send) };
-                let _ = write!(&mut f, "{}\n", &text);
-                f.flush().unwrap();
-                io::close(send, "here_data close error (child send)");
-                process::exit(0);
-            },
-            ForkResult::Parent { child: _ } => {
-                io::close(send, "here_data close error (parent send)");
-                io::replace(recv, 0);
-            },
+    // A small body is passed through a pipe and a large one through an
+    // unlinked temporary file so that the shell never blocks on writing.
+    fn here_data_fd(text: &[u8]) -> Result<RawFd, ExecError> {
+        if text.len() <= HERE_PIPE_SIZE {
+            let (recv, send) = unistd::pipe()?;
+            let mut f = File::from(send);
+            if f.write_all(text).is_err() {
+                return Err(ExecError::Other("cannot write here-document".to_string()));
+            }
+            return Ok(recv.into_raw_fd());
         }
-        Ok(())
+
+        let mut f = match io::anonymous_file("here") {
+            Ok(f) => f,
+            Err(e) => return Err(ExecError::Other(format!("cannot create temp file for here-document: {}", e.kind()))),
+        };
+        if f.write_all(text).and_then(|_| f.rewind()).is_err() {
+            return Err(ExecError::Other("cannot write here-document".to_string()));
+        }
+        Ok(f.into_raw_fd())
+    }
+
+    pub fn changed_fds(&self) -> Vec<RawFd> {
+        vec![self.left_fd, self.moved_fd].into_iter().filter(|fd| *fd >= 0).collect()
     }
 
     pub fn restore(&mut self) {
@@ -249,10 +342,23 @@ impl Redirect {
         if self.extra_left_backup >= 0 {
             io::replace(self.extra_left_backup, 2);
         }
+        if self.moved_fd_backup >= 0 && self.moved_fd_backup != self.moved_fd {
+            io::replace(self.moved_fd_backup, self.moved_fd);
+        }
+        self.left_backup = -1;
+        self.extra_left_backup = -1;
+        self.moved_fd_backup = -1;
     }
 
     pub fn new() -> Redirect {
-        Redirect { left_fd: -1, left_backup: -1, extra_left_backup: -1, ..Default::default() }
+        Redirect {
+            left_fd: -1,
+            left_backup: -1,
+            extra_left_backup: -1,
+            moved_fd: -1,
+            moved_fd_backup: -1,
+            ..Default::default()
+        }
     }
 
     pub fn eat_heredoc(&mut self, feeder: &mut Feeder, core: &mut ShellCore) -> Result<(), ParseError> {
@@ -261,45 +367,96 @@ impl Redirect {
             Ok(s) => s,
             Err(_) => return Err(ParseError::UnexpectedSymbol(self.right.text.clone())),
         };
-
-        let end_return = end.clone() + "\n";
+        let quoted = self.right.make_unquoted_word().unwrap_or_default() != self.right.text;
+        let lineno = feeder.lineno;
 
         if feeder.starts_with("\n") {
             feeder.consume(1);
         }
 
+        let mut body = String::new();
         loop {
-            if feeder.len() == 0 {
-                feeder.feed_additional_line(core)?;
-
-                if remove_tab {
-                    let len = feeder.scanner_tabs();
-                    feeder.consume(len);
-                }
-
-                if feeder.starts_with(&end_return) {
-                    feeder.consume(end.len());
+            let line = match Self::read_heredoc_line(feeder, core, remove_tab, quoted)? {
+                Some(line) => line,
+                None => {
+                    let shellname = core.db.get_param("0").unwrap_or_default();
+                    eprintln!(
+                        "{}: line {}: warning: here-document at line {} delimited by end-of-file (wanted `{}')",
+                        &shellname,
+                        feeder.lineno.saturating_sub(1),
+                        lineno.saturating_sub(1),
+                        &end
+                    );
                     break;
+                },
+            };
+
+            if line.strip_suffix("\n").unwrap_or(&line) == end {
+                if line.ends_with("\n") {
+                    feeder.replace(0, "\n"); // the end of the command line
                 }
+                break;
             }
+            body += &line;
+        }
 
-            if let Some(mut sw) = subword::parse(feeder, core, &Some(WordMode::Heredoc))? {
+        self.here_data.text = body.clone();
+        if quoted {
+            return Ok(());
+        }
+
+        let mut f = Feeder::new(&body);
+        while !f.is_empty() {
+            if let Some(mut sw) = subword::parse(&mut f, core, &Some(WordMode::Heredoc))? {
                 sw.set_heredoc_flag();
-                self.here_data.text += sw.get_text();
                 self.here_data.subwords.push(sw);
             } else {
-                let len = feeder.scanner_char();
-                if len > 0 {
-                    let c = feeder.consume(len);
-                    self.here_data.text += &c;
-                    self.here_data.subwords.push(Box::new(FillerSubword { text: c }));
-                }
+                let len = f.scanner_char();
+                let c = f.consume(len);
+                self.here_data.subwords.push(Box::new(FillerSubword { text: c }));
             }
         }
 
         Ok(())
     }
 
+    fn read_heredoc_line(
+        feeder: &mut Feeder,
+        core: &mut ShellCore,
+        remove_tab: bool,
+        quoted: bool,
+    ) -> Result<Option<String>, ParseError> {
+        let mut line = String::new();
+        loop {
+            if feeder.len() == 0 {
+                match feeder.feed_additional_line(core) {
+                    Ok(()) => {},
+                    Err(ParseError::Input(InputError::Eof)) if line.is_empty() => return Ok(None),
+                    Err(ParseError::Input(InputError::Eof)) => return Ok(Some(line)),
+                    Err(e) => return Err(e),
+                }
+            }
+
+            if remove_tab && line.is_empty() {
+                let len = feeder.scanner_tabs();
+                feeder.consume(len);
+            }
+
+            let len = feeder.scanner_line();
+            let part = feeder.consume(len);
+
+            let escaped = part.trim_end_matches('\n');
+            let backslashes = escaped.len() - escaped.trim_end_matches('\\').len();
+            if !quoted && part.ends_with("\\\n") && backslashes % 2 == 1 {
+                line += &part[..part.len() - 2]; // line continuation
+                continue;
+            }
+
+            line += &part;
+            return Ok(Some(line));
+        }
+    }
+
     fn eat_symbol(feeder: &mut Feeder, ans: &mut Self, core: &mut ShellCore) -> bool {
         let len = feeder.scanner_redirect_symbol(core);
         if len == 0 {
@@ -325,7 +482,28 @@ impl Redirect {
         true
     }
 
+    fn eat_fd_var(feeder: &mut Feeder, ans: &mut Self, core: &mut ShellCore) -> bool {
+        ans.left = feeder.consume(1);
+        let len = feeder.scanner_name(core);
+        if len == 0 {
+            return false;
+        }
+
+        ans.left += &feeder.consume(len);
+        if !feeder.starts_with("}") {
+            return false;
+        }
+
+        ans.left += &feeder.consume(1);
+        ans.text += &ans.left.clone();
+        true
+    }
+
     fn eat_left(feeder: &mut Feeder, ans: &mut Self, core: &mut ShellCore) -> bool {
+        if feeder.starts_with("{") {
+            return Self::eat_fd_var(feeder, ans, core);
+        }
+
         let len = feeder.scanner_uint(core);
         if len == 0 {
             return true; //左側なし（文法上OK）
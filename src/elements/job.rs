@@ -1,10 +1,18 @@
 // SPDX-FileCopyrightText: 2022 Ryuichi Ueda ryuichiueda@gmail.com
 // SPDX-License-Identifier: BSD-3-Clause
 
-use std::sync::atomic::Ordering::Relaxed;
+use std::{
+    os::fd::{AsRawFd, OwnedFd},
+    sync::atomic::Ordering::Relaxed,
+};
 
 use nix::{
-    sys::wait::WaitStatus,
+    errno::Errno,
+    fcntl::OFlag,
+    sys::{
+        signal::{Signal, raise},
+        wait::WaitStatus,
+    },
     unistd,
     unistd::{ForkResult, Pid},
 };
@@ -40,13 +48,30 @@ impl Job {
     }
 
     fn exec_fg(&mut self, core: &mut ShellCore, pgid: Pid) -> Result<(), ExecError> {
+        core.fg_depth += 1;
+        let result = self.exec_pipelines(core, pgid);
+        core.fg_depth -= 1;
+
+        if core.fg_depth == 0 {
+            if core.runs_suspended_rest {
+                if let Err(e) = result {
+                    e.print(core);
+                }
+                exit::normal(core);
+            }
+            self.register_suspended(core);
+        }
+        result
+    }
+
+    fn exec_pipelines(&mut self, core: &mut ShellCore, pgid: Pid) -> Result<(), ExecError> {
         let mut do_next = true;
         let susp_e_option = core.suspend_e_option;
 
         signal::check_trap(core);
 
         for (pipeline, end) in self.pipelines.iter_mut().zip(self.pipeline_ends.iter()) {
-            if core.return_flag {
+            if core.return_flag || core.suspended_job.is_some() {
                 break;
             }
             if core.sigint.load(Relaxed) {
@@ -57,10 +82,14 @@ impl Job {
             core.suspend_e_option = susp_e_option || end == "&&" || end == "||";
             if do_next {
                 core.jobtable_check_status()?;
+                let pgid = match core.is_subshell {
+                    true => unistd::getpgrp(),
+                    false => pgid,
+                };
                 let (pids, exclamation, time, err) = pipeline.exec(core, pgid);
                 let waitstatuses = proc_ctrl::wait_pipeline(core, pids.clone(), exclamation, time);
 
-                Self::check_stop(core, &pipeline.get_one_line_text(), &pids, &waitstatuses);
+                Self::check_stop(core, pipeline, &pids, &waitstatuses);
 
                 if err.is_some() {
                     return Err(err.unwrap());
@@ -74,22 +103,93 @@ impl Job {
         Ok(())
     }
 
-    fn check_stop(core: &mut ShellCore, text: &str, pids: &Vec<Option<Pid>>, waitstatuses: &Vec<WaitStatus>) {
+    fn check_stop(core: &mut ShellCore, pipeline: &Pipeline, pids: &Vec<Option<Pid>>, waitstatuses: &Vec<WaitStatus>) {
         if core.is_subshell || pids.is_empty() || pids[0] == None {
             return;
         }
 
         for ws in waitstatuses {
             if let WaitStatus::Stopped(..) = ws {
+                if core.fg_depth > 1 {
+                    Self::suspend(core, pids.iter().flatten().copied().collect(), waitstatuses);
+                    return;
+                }
+
                 let new_job_id = core.generate_new_job_id();
-                let job = JobEntry::new(pids.to_vec(), &waitstatuses, &text, "Stopped", new_job_id);
+                let text = pipeline.get_one_line_text();
+                let mut job = JobEntry::new(pids.to_vec(), &waitstatuses, &text, "Stopped", new_job_id);
+                if pids.iter().all(|p| p.is_some()) {
+                    job.commands = pipeline.get_command_texts();
+                }
                 core.job_table_priority.insert(0, new_job_id);
                 core.job_table.push(job);
+
+                println!();
+                core.jobtable_print_status_change();
                 return;
             }
         }
     }
 
+    /* A command stopped inside a function or a compound command run by the shell
+     * itself. A child forked here stops in the process group of the command and
+     * runs the rest when the command ends, and the shell leaves the compound command. */
+    fn suspend(core: &mut ShellCore, pids: Vec<Pid>, waitstatuses: &[WaitStatus]) {
+        let pgid = unistd::getpgid(Some(pids[0])).unwrap_or(pids[0]);
+        let (recv, send) = match unistd::pipe2(OFlag::O_CLOEXEC) {
+            Ok(p) => p,
+            Err(_) => return,
+        };
+
+        match unsafe { unistd::fork() } {
+            Ok(ForkResult::Child) => {
+                core.initialize_as_subshell(Pid::from_raw(0), pgid);
+                core.runs_suspended_rest = true;
+                drop(send);
+                let _ = raise(Signal::SIGSTOP);
+                if let Some(status) = Self::read_status(recv) {
+                    core.db.exit_status = status;
+                }
+            },
+            Ok(ForkResult::Parent { child }) => {
+                proc_ctrl::set_pgid(core, child, pgid);
+                core.suspended_job = Some(JobEntry::new_suspended(pids, waitstatuses, child, send));
+            },
+            Err(_) => {},
+        }
+    }
+
+    fn read_status(fd: OwnedFd) -> Option<i32> {
+        let mut buf = [0u8; 16];
+        loop {
+            match unistd::read(fd.as_raw_fd(), &mut buf) {
+                Ok(n) => return String::from_utf8_lossy(&buf[..n]).parse::<i32>().ok(),
+                Err(Errno::EINTR) => continue,
+                Err(_) => return None,
+            }
+        }
+    }
+
+    fn register_suspended(&self, core: &mut ShellCore) {
+        let mut job = match core.suspended_job.take() {
+            Some(j) => j,
+            None => return,
+        };
+
+        job.id = core.generate_new_job_id();
+        job.text = self.get_one_line_text().trim_end().to_string();
+        job.commands = vec![job.text.clone()];
+        if !core.options.query("monitor") {
+            job.no_control = true;
+        }
+        core.job_table_priority.insert(0, job.id);
+        core.job_table.push(job);
+        core.db.exit_status = 148;
+
+        println!();
+        core.jobtable_print_status_change();
+    }
+
     fn exec_bg(&mut self, core: &mut ShellCore, pgid: Pid) {
         let backup = match core.tty_fd.as_ref() {
             Some(fd) => Some(fd.try_clone().unwrap()),
@@ -97,10 +197,12 @@ impl Job {
         };
         core.tty_fd = None;
 
+        let mut commands = vec![];
         let pids = if self.pipelines.len() == 1 {
             if self.pipelines[0].commands.len() == 1 {
                 self.pipelines[0].commands[0].set_force_fork();
             }
+            commands = self.pipelines[0].get_command_texts();
             self.pipelines[0].exec(core, pgid).0
         } else {
             match self.exec_fork_bg(core, pgid) {
@@ -122,6 +224,10 @@ impl Job {
         if !core.options.query("monitor") {
             entry.no_control = true;
         }
+        if commands.len() == entry.pids.len() {
+            entry.commands = commands;
+        }
+        core.jobtable_forget_saved_status(&entry.pids);
 
         core.job_table.push(entry);
 
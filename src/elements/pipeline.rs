@@ -1,11 +1,12 @@
 // SPDX-FileCopyrightText: 2022 Ryuichi Ueda ryuichiueda@gmail.com
 // SPDX-License-Identifier: BSD-3-Clause
 
-use nix::{sys::resource, time, time::ClockId, unistd::Pid};
+use nix::unistd::Pid;
 
 use super::{Pipe, command, command::Command};
 use crate::{
     Feeder, ShellCore,
+    core::MeasuredTime,
     error::{exec::ExecError, parse::ParseError},
 };
 
@@ -16,28 +17,40 @@ pub struct Pipeline {
     pub text:     String,
     exclamation:  bool,
     pub time:     bool,
+    time_posix:   bool,
+}
+
+fn starts_with_word(feeder: &Feeder, word: &str) -> bool {
+    feeder.len() == word.len() && feeder.starts_with(word)
+        || [" ", "\t", "\n", ";", "&", "("].iter().any(|d| feeder.starts_with(&(word.to_owned() + d)))
 }
 
 impl Pipeline {
-    pub fn exec(&mut self, core: &mut ShellCore, pgid: Pid) -> (Vec<Option<Pid>>, bool, bool, Option<ExecError>) {
+    pub fn exec(
+        &mut self,
+        core: &mut ShellCore,
+        pgid: Pid,
+    ) -> (Vec<Option<Pid>>, bool, Option<MeasuredTime>, Option<ExecError>) {
+        let time = match self.time {
+            true => Some(MeasuredTime::start(self.time_posix)),
+            false => None,
+        };
+
         if self.commands.is_empty() {
-            // the case of only '!'
-            self.set_time(core);
-            return (vec![], self.exclamation, self.time, None);
+            // the case of only '!' or 'time'
+            return (vec![], self.exclamation, time, None);
         }
 
         let mut prev = -1;
         let mut pids = vec![];
         let mut pgid = pgid;
 
-        self.set_time(core);
-
         for (i, p) in self.pipes.iter_mut().enumerate() {
             p.set(prev, pgid);
 
             match self.commands[i].exec(core, p) {
                 Ok(pid) => pids.push(pid),
-                Err(e) => return (pids, self.exclamation, self.time, Some(e)),
+                Err(e) => return (pids, self.exclamation, time, Some(e)),
             }
 
             if i == 0 && pgid.as_raw() == 0 {
@@ -56,23 +69,10 @@ impl Pipeline {
 
         match result {
             Ok(pid) => pids.push(pid),
-            Err(e) => return (pids, self.exclamation, self.time, Some(e)),
-        }
-
-        (pids, self.exclamation, self.time, None)
-    }
-
-    fn set_time(&mut self, core: &mut ShellCore) {
-        if !self.time {
-            return;
+            Err(e) => return (pids, self.exclamation, time, Some(e)),
         }
 
-        let self_usage = resource::getrusage(resource::UsageWho::RUSAGE_SELF).unwrap();
-        let children_usage = resource::getrusage(resource::UsageWho::RUSAGE_CHILDREN).unwrap();
-
-        core.measured_time.user = self_usage.user_time() + children_usage.user_time();
-        core.measured_time.sys = self_usage.system_time() + children_usage.system_time();
-        core.measured_time.real = time::clock_gettime(ClockId::CLOCK_MONOTONIC).unwrap();
+        (pids, self.exclamation, time, None)
     }
 
     pub fn read_heredoc(&mut self, feeder: &mut Feeder, core: &mut ShellCore) -> Result<(), ParseError> {
@@ -98,6 +98,10 @@ impl Pipeline {
         ans
     }
 
+    pub fn get_command_texts(&self) -> Vec<String> {
+        self.commands.iter().map(|c| c.get_one_line_text().trim().to_string()).collect()
+    }
+
     fn eat_exclamation(feeder: &mut Feeder, ans: &mut Self, core: &mut ShellCore) -> bool {
         match feeder.starts_with("!") {
             true => ans.text += &feeder.consume(1),
@@ -111,7 +115,7 @@ impl Pipeline {
     }
 
     fn eat_time(feeder: &mut Feeder, ans: &mut Self, core: &mut ShellCore) -> bool {
-        match feeder.starts_with("time") {
+        match starts_with_word(feeder, "time") {
             true => ans.text += &feeder.consume(4),
             false => return false,
         }
@@ -119,6 +123,16 @@ impl Pipeline {
         ans.time = true;
         let blank_len = feeder.scanner_blank(core);
         ans.text += &feeder.consume(blank_len);
+
+        for opt in ["-p", "--"] {
+            // like bash, "--" also selects the POSIX format
+            if starts_with_word(feeder, opt) {
+                ans.text += &feeder.consume(2);
+                ans.time_posix = true;
+                let blank_len = feeder.scanner_blank(core);
+                ans.text += &feeder.consume(blank_len);
+            }
+        }
         true
     }
 
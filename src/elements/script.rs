@@ -150,7 +150,14 @@ impl Script {
     pub fn parse(feeder: &mut Feeder, core: &mut ShellCore, permit_empty: bool) -> Result<Option<Script>, ParseError> {
         let mut ans = Self::default();
         loop {
-            while Self::eat_job(feeder, core, &mut ans)? && Self::eat_job_end(feeder, &mut ans) {}
+            while Self::eat_job(feeder, core, &mut ans)? {
+                if feeder.starts_with("\n") && feeder.len() > 1 {
+                    ans.read_heredoc(feeder, core)?; // here-documents given in a string (eval etc.)
+                }
+                if !Self::eat_job_end(feeder, &mut ans) {
+                    break;
+                }
+            }
 
             match ans.check_nest(feeder, permit_empty) {
                 Status::NormalEnd => {
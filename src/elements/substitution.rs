@@ -9,7 +9,9 @@ pub mod variable;
 use self::{value::Value, variable::Variable};
 use crate::{
     Feeder, ShellCore,
+    elements::expr::arithmetic::elem::int::{self, OverflowMode},
     error::{exec::ExecError, parse::ParseError},
+    utils,
 };
 
 #[derive(Debug, Clone, Default)]
@@ -96,6 +98,11 @@ impl Substitution {
             return Err(ExecError::ArrayIndexInvalid(self.left_hand.text.clone()));
         }
         if let Some(v) = &self.right_hand.evaluated_string {
+            if self.append && core.db.has_flag(&self.left_hand.name, 'i') {
+                let cur = core.db.get_elem(&self.left_hand.name, index)?;
+                let sum = Self::add_as_int(&self.left_hand.text, &cur, v, core)?;
+                return core.db.set_param2(&self.left_hand.name, index, &sum, Some(layer));
+            }
             if self.append {
                 return core.db.append_param2(&self.left_hand.name, index, &v, Some(layer));
             } else {
@@ -118,6 +125,11 @@ impl Substitution {
 
     fn set_single(&mut self, core: &mut ShellCore, layer: usize) -> Result<(), ExecError> {
         let data = self.right_hand.evaluated_string.clone().unwrap();
+        if self.append && core.db.has_flag(&self.left_hand.name, 'i') {
+            let cur = core.db.get_param(&self.left_hand.name)?;
+            let sum = Self::add_as_int(&self.left_hand.name, &cur, &data, core)?;
+            return core.db.set_param(&self.left_hand.name, &sum, Some(layer));
+        }
         if self.append {
             core.db.append_param(&self.left_hand.name, &data, Some(layer))
         } else {
@@ -125,6 +137,19 @@ impl Substitution {
         }
     }
 
+    fn add_as_int(name: &str, cur: &str, data: &str, core: &mut ShellCore) -> Result<String, ExecError> {
+        let cur = if cur.is_empty() { "0" } else { cur };
+        let left = utils::string_to_calculated_string(cur, core)?;
+        let right = utils::string_to_calculated_string(data, core)?;
+        match (left.parse::<i128>(), right.parse::<i128>()) {
+            (Ok(l), Ok(r)) => match int::calc("+", l, r, OverflowMode::get(core)) {
+                Ok(n) => Ok(n.to_string()),
+                Err(e) => Err(ExecError::ArithError(name.to_string(), e)),
+            },
+            _ => utils::string_to_calculated_string(&format!("{}+({})", left, right), core),
+        }
+    }
+
     fn set_to_shell(&mut self, core: &mut ShellCore, layer: Option<usize>) -> Result<(), ExecError> {
         let layer = core.db.get_target_layer(&self.left_hand.name, layer);
 
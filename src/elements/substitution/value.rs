@@ -38,7 +38,7 @@ impl Value {
     }
 
     fn eval_as_value(&mut self, w: &Word, core: &mut ShellCore, name: &str) -> Result<(), ExecError> {
-        self.evaluated_string = match core.db.has_flag(&name, 'i') {
+        self.evaluated_string = match core.db.has_flag(name, 'i') || core.db.has_flag(name, 'E') {
             true => Some(w.eval_as_integer(core)?),
             false => Some(w.eval_as_value(core)?),
         };
@@ -124,6 +124,10 @@ impl Variable {
             _ => prev[0].clone(),
         };
 
+        if arg::consume_option("-E", args) {
+            return core.db.init_as_float(&self.name, &value, layer);
+        }
+
         match i_opt {
             true => core.db.init_as_num(&self.name, &value, layer),
             false => core.db.set_param(&self.name, &value, layer),
@@ -133,7 +137,7 @@ impl Variable {
     pub fn exist(&self, core: &mut ShellCore) -> Result<bool, ExecError> {
         // used in value_check.rs
         if core.db.is_array(&self.name) || core.db.is_assoc(&self.name) {
-            if core.db.get_vec(&self.name, false)?.is_empty() {
+            if core.db.len(&self.name) == 0 {
                 return Ok(false);
             }
 
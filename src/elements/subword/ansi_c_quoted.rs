@@ -60,7 +60,7 @@ impl AnsiCQuoted {
         let mut ans = Self::default();
         ans.text += &feeder.consume(2);
 
-        if let Some(ansi_c_str) = AnsiCString::parse(feeder, core, false)? {
+        if let Some(ansi_c_str) = AnsiCString::parse(feeder, core)? {
             ans.text += &ansi_c_str.text;
             ans.tokens = ansi_c_str.tokens;
         }
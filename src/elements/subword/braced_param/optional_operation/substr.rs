@@ -126,11 +126,9 @@ impl Substr {
             }
         }
 
-        // let start = std::cmp::max(0, n) as usize;
-        //*array = core.db.get_vec_from(name, start, true)?;
-        *array = core.db.get_vec_from(name, n as usize, true)?;
-
+        let start = n as usize;
         if self.length.is_none() {
+            *array = core.db.get_vec_from(name, start, true)?;
             *text = array.join(" ");
             return Ok(());
         }
@@ -148,8 +146,7 @@ impl Substr {
         if n < 0 {
             return Err(ExecError::SubstringMinus(n));
         }
-        let len = std::cmp::min(n as usize, array.len());
-        let _ = array.split_off(len);
+        *array = core.db.get_vec_range(name, start, n as usize)?;
 
         *text = array.join(" ");
         Ok(())
@@ -62,7 +62,7 @@ impl BracedParam {
     }
 
     pub fn parse(feeder: &mut Feeder, core: &mut ShellCore) -> Result<Option<Self>, ParseError> {
-        if !feeder.starts_with("${") {
+        if !feeder.starts_with("${") || feeder.starts_with("${ ") || feeder.starts_with("${\t") || feeder.starts_with("${\n") {
             return Ok(None);
         }
         let mut ans = Self::default();
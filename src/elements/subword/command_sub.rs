@@ -3,8 +3,8 @@
 
 use std::{
     fs::File,
-    io::{BufRead, BufReader, Error},
-    os::fd::{FromRawFd, RawFd},
+    io::{ErrorKind, Read, Seek, Write, stdout},
+    os::fd::{AsRawFd, FromRawFd, IntoRawFd, RawFd},
     sync::atomic::Ordering::Relaxed,
     thread, time,
 };
@@ -15,17 +15,24 @@ use crate::{
     Feeder, ShellCore,
     elements::{
         Pipe,
+        command,
         command::{Command, paren::ParenCommand},
+        io,
+        script::Script,
         subword::Subword,
     },
+    error,
     error::{exec::ExecError, parse::ParseError},
     proc_ctrl,
 };
 
+const READ_SIZE: usize = 65536;
+
 #[derive(Debug, Clone, Default)]
 pub struct CommandSubstitution {
     pub text: String,
     command:  ParenCommand,
+    script:   Option<Script>, // for ${ command; }
 }
 
 impl Subword for CommandSubstitution {
@@ -37,11 +44,17 @@ impl Subword for CommandSubstitution {
     }
 
     fn substitute(&mut self, core: &mut ShellCore) -> Result<(), ExecError> {
+        if self.script.is_some() {
+            self.substitute_nofork(core)?;
+            self.text = self.text.trim_end_matches("\n").to_string();
+            return Ok(());
+        }
+
         let mut pipe = Pipe::new("|".to_string());
         pipe.set(-1, unistd::getpgrp());
         let pid = self.command.exec(core, &mut pipe)?;
         let result = self.read(pipe.recv, core);
-        proc_ctrl::wait_pipeline(core, vec![pid], false, false);
+        proc_ctrl::wait_pipeline(core, vec![pid], false, None);
         result?;
         self.text = self.text.trim_end_matches("\n").to_string();
         Ok(())
@@ -49,13 +62,63 @@ impl Subword for CommandSubstitution {
 }
 
 impl CommandSubstitution {
-    fn set_line(&mut self, line: Result<String, Error>) -> bool {
-        if let Ok(ln) = line {
-            self.text.push_str(&ln);
-            self.text.push('\n');
-            return true;
+    fn substitute_nofork(&mut self, core: &mut ShellCore) -> Result<(), ExecError> {
+        let mut f = match io::anonymous_file("funsub") {
+            Ok(f) => f,
+            Err(e) => {
+                let msg = format!("cannot make temp file for command substitution: {}", e.kind());
+                return Err(ExecError::Other(msg));
+            },
+        };
+
+        let _ = stdout().flush();
+        let backup = io::backup(1);
+        unistd::dup2(f.as_raw_fd(), 1)?;
+
+        let result = self.script.as_mut().unwrap().exec(core);
+
+        let _ = stdout().flush();
+        match backup {
+            1 => io::close(1, "sush(fatal): 1: cannot be closed"),
+            _ => {
+                io::replace(backup, 1);
+            },
+        }
+        result?;
+
+        if f.rewind().is_err() {
+            return Err(ExecError::Other("cannot read command substitution output".to_string()));
         }
-        false
+        self.read(f.into_raw_fd(), core)
+    }
+
+    // Invalid UTF-8 bytes are kept as U+E080..U+E0FF, which c_string::to_carg turns back into raw bytes.
+    fn decode(&mut self, bytes: &mut Vec<u8>, last: bool) {
+        let mut pos = 0;
+        while pos < bytes.len() {
+            match std::str::from_utf8(&bytes[pos..]) {
+                Ok(s) => {
+                    self.text.push_str(s);
+                    pos = bytes.len();
+                },
+                Err(e) => {
+                    let valid = pos + e.valid_up_to();
+                    self.text.push_str(unsafe { std::str::from_utf8_unchecked(&bytes[pos..valid]) });
+                    pos = valid;
+
+                    let len = match e.error_len() {
+                        Some(n) => n,
+                        None if !last => break,
+                        None => bytes.len() - pos,
+                    };
+                    for b in &bytes[pos..pos + len] {
+                        self.text.push(char::from_u32(0xE000 + *b as u32).unwrap());
+                    }
+                    pos += len;
+                },
+            }
+        }
+        bytes.drain(..pos);
     }
 
     fn interrupted(&mut self, count: usize, core: &mut ShellCore) -> Result<(), ExecError> {
@@ -70,20 +133,53 @@ impl CommandSubstitution {
     }
 
     fn read(&mut self, fd: RawFd, core: &mut ShellCore) -> Result<(), ExecError> {
-        let f = unsafe { File::from_raw_fd(fd) };
-        let reader = BufReader::new(f);
+        let mut f = unsafe { File::from_raw_fd(fd) };
+        let mut buf = vec![0; READ_SIZE];
+        let mut rest = vec![];
+        let mut null_warned = false;
         self.text.clear();
-        for (i, line) in reader.lines().enumerate() {
+
+        for i in 0.. {
             self.interrupted(i, core)?;
-            if !self.set_line(line) {
-                break;
+            let len = match f.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            };
+
+            if buf[..len].contains(&0) {
+                if !null_warned {
+                    error::print("warning: command substitution: ignored null byte in input", core);
+                    null_warned = true;
+                }
+                rest.extend(buf[..len].iter().filter(|b| **b != 0));
+            } else {
+                rest.extend_from_slice(&buf[..len]);
             }
+            self.decode(&mut rest, false);
         }
 
-        self.text.pop();
+        self.decode(&mut rest, true);
         Ok(())
     }
 
+    fn parse_nofork(feeder: &mut Feeder, core: &mut ShellCore) -> Result<Option<Self>, ParseError> {
+        if !feeder.starts_with("${ ") && !feeder.starts_with("${\t") && !feeder.starts_with("${\n") {
+            return Ok(None);
+        }
+
+        let mut ans = Self::default();
+        if command::eat_inner_script(feeder, core, "${", vec!["}"], &mut ans.script, false)? {
+            ans.text = "${".to_string();
+            ans.text += &ans.script.as_ref().unwrap().get_text();
+            ans.text += &feeder.consume(1);
+            return Ok(Some(ans));
+        }
+
+        Ok(None)
+    }
+
     pub fn parse_old_style(feeder: &mut Feeder, core: &mut ShellCore) -> Result<Option<Self>, ParseError> {
         if !feeder.starts_with("`") {
             return Ok(None);
@@ -135,6 +231,9 @@ impl CommandSubstitution {
         if let Some(ans) = Self::parse_old_style(feeder, core)? {
             return Ok(Some(ans));
         }
+        if let Some(ans) = Self::parse_nofork(feeder, core)? {
+            return Ok(Some(ans));
+        }
 
         if !feeder.starts_with("$(") {
             return Ok(None);
@@ -40,7 +40,7 @@ impl Subword for DoubleQuoted {
         self.text.clear();
 
         for (i, sw) in self.subwords.iter_mut().enumerate() {
-            if self.split_points.contains(&i) {
+            if self.split_points.binary_search(&i).is_ok() {
                 self.text += " ";
             }
             self.text += sw.get_text();
@@ -74,7 +74,7 @@ impl Subword for DoubleQuoted {
         let mut text = String::new();
 
         for (i, sw) in self.subwords.iter_mut().enumerate() {
-            if self.split_points.contains(&i) {
+            if self.split_points.binary_search(&i).is_ok() {
                 text += " ";
             }
 
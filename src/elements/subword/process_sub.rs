@@ -6,7 +6,7 @@ use nix::unistd;
 use crate::{
     Feeder, ShellCore,
     elements::{
-        Pipe,
+        Pipe, io,
         command::{Command, paren::ParenCommand},
         subword::Subword,
         word::WordMode,
@@ -30,14 +30,20 @@ impl Subword for ProcessSubstitution {
     }
 
     fn substitute(&mut self, core: &mut ShellCore) -> Result<(), ExecError> {
-        if self.direction != '<' {
-            return Err(ExecError::Other(">() is not supported yet".to_string()));
+        let mut pipe = Pipe::new("|".to_string());
+        match self.direction {
+            '<' => pipe.set(-1, unistd::getpgrp()),
+            _ => pipe.set_reverse(unistd::getpgrp()),
         }
 
-        let mut pipe = Pipe::new("|".to_string());
-        pipe.set(-1, unistd::getpgrp());
-        let _ = self.command.exec(core, &mut pipe)?;
-        self.text = "/dev/fd/".to_owned() + &pipe.recv.to_string();
+        let pid = self.command.exec(core, &mut pipe)?;
+        let fd = io::move_out_of_user_range(pipe.recv, false);
+        core.fds.proc_sub.push(fd);
+        if let Some(pid) = pid {
+            core.db.set_param("!", &pid.to_string(), None)?;
+        }
+
+        self.text = "/dev/fd/".to_owned() + &fd.to_string();
         Ok(())
     }
 }
@@ -13,24 +13,26 @@ pub fn eval(word: &Word, core: &mut ShellCore) -> Vec<Word> {
 
     let ifs = core.db.get_param("IFS").unwrap();
 
-    let (pos, mut split) = find_pos(word, &ifs);
+    let (pos, split) = find_pos(word, &ifs);
     if split.is_empty() {
         return vec![word.clone()];
     }
 
+    let len = split.len();
+    let mut split = split.into_iter();
+
     let mut left = word.subwords[..pos].to_vec();
-    let remain = split[0].1;
-    left.push(split.remove(0).0);
+    let (sw, remain) = split.next().unwrap();
+    left.push(sw);
     let mut ans = vec![gen_word(left, remain)];
 
-    while split.len() >= 2 {
-        let remain = split[0].1;
-        ans.push(gen_word(vec![split.remove(0).0], remain));
+    for (sw, remain) in split.by_ref().take(len - 2) {
+        ans.push(gen_word(vec![sw], remain));
     }
 
-    let remain = split[0].1;
+    let (sw, remain) = split.next().unwrap();
     let mut right = gen_word(word.subwords[pos + 1..].to_vec(), remain);
-    right.subwords.insert(0, split.remove(0).0);
+    right.subwords.insert(0, sw);
 
     [ans, eval(&right, core)].concat()
 }
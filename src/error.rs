@@ -24,10 +24,6 @@ pub fn internal(s: &str) -> String {
     format!("SUSH INTERNAL ERROR: {}", s)
 }
 
-pub fn exponent(s: &str) -> String {
-    format!("exponent less than 0 (error token is \"{}\")", s)
-}
-
 // error at wait
 pub fn signaled(pid: Pid, signal: Signal, coredump: bool) -> i32 {
     match coredump {
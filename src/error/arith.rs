@@ -13,9 +13,12 @@ pub enum ArithError {
     InvalidNumber(String),
     InvalidIntConst(String),
     InvalidOperator(String),
+    MathDomain(String),
     OperandExpected(String),
+    Overflow(String),
     Recursion(String),
     SyntaxError(String),
+    WrongArgCount(String),
 }
 
 impl From<ArithError> for String {
@@ -36,10 +39,13 @@ impl From<&ArithError> for String {
             ArithError::ValueTooGreatForBase(num) => error_msg("value too great for base", num),
             ArithError::InvalidNumber(name) => error_msg("invalid number", name),
             ArithError::InvalidIntConst(tok) => error_msg("invalid integer constant", tok),
-            ArithError::InvalidOperator(tok) => error_msg("invalid arithmetic operator", tok),
+            ArithError::InvalidOperator(tok) => error_msg("syntax error: invalid arithmetic operator", tok),
+            ArithError::MathDomain(token) => error_msg("math domain error", token),
             ArithError::OperandExpected(token) => error_msg("syntax error: operand expected", token),
+            ArithError::Overflow(token) => error_msg("integer overflow", token),
             ArithError::Recursion(token) => error_msg("expression recursion level exceeded", token),
             ArithError::SyntaxError(token) => error_msg("syntax error in expression", token),
+            ArithError::WrongArgCount(token) => error_msg("wrong number of arguments", token),
         }
     }
 }
@@ -2,16 +2,18 @@
 // SPDX-License-Identifier: BSD-3-Clause
 
 mod scanner;
-mod terminal;
+pub mod terminal;
 
 use std::{
     fs::File,
     io::{BufRead, BufReader, Lines},
+    os::fd::{FromRawFd, IntoRawFd, RawFd},
     sync::atomic::Ordering::Relaxed,
 };
 
 use crate::{
     ShellCore,
+    elements::io,
     error::{input::InputError, parse::ParseError},
     utils,
 };
@@ -44,13 +46,15 @@ impl Feeder {
         }
     }
 
-    pub fn set_file(&mut self, s: &str) -> Result<(), InputError> {
+    pub fn set_file(&mut self, s: &str) -> Result<RawFd, InputError> {
         let file = match File::open(s) {
             Ok(f) => f,
             Err(_) => return Err(InputError::NoSuchFile(s.to_string())),
         };
+        let fd = io::move_out_of_user_range(file.into_raw_fd(), true);
+        let file = unsafe { File::from_raw_fd(fd) };
         self.script_lines = Some(BufReader::new(file).lines());
-        Ok(())
+        Ok(fd)
     }
 
     pub fn consume(&mut self, cutpos: usize) -> String {
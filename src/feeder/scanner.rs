@@ -353,7 +353,7 @@ impl Feeder {
 
     pub fn scanner_redirect_symbol(&mut self, core: &mut ShellCore) -> usize {
         self.backslash_check_and_feed(vec!["<<", ">", "&", "<"], core);
-        self.scanner_one_of(&["<<<", "<<-", "&>", ">&", "<&", ">>", "<<", "<", ">"])
+        self.scanner_one_of(&["<<<", "<<-", "&>>", "&>", ">&", "<&", ">>", ">|", "<>", "<<", "<", ">"])
     }
 
     pub fn scanner_parameter_alternative_symbol(&mut self) -> usize {
@@ -364,8 +364,15 @@ impl Feeder {
         self.scanner_one_of(&["##", "#", "%%", "%"])
     }
 
+    pub fn scanner_line(&self) -> usize {
+        match self.remaining.find('\n') {
+            Some(n) => n + 1,
+            None => self.remaining.len(),
+        }
+    }
+
     pub fn scanner_tabs(&mut self) -> usize {
-        self.scanner_one_of(&["\t"])
+        self.remaining.len() - self.remaining.trim_start_matches('\t').len()
     }
 
     pub fn scanner_test_check_option(&mut self, core: &mut ShellCore) -> usize {
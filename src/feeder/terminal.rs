@@ -7,12 +7,22 @@ mod key;
 use std::{
     fs::File,
     io,
-    io::{Stdout, Write},
+    io::{ErrorKind, Read, Stdout, Write},
+    os::fd::AsFd,
     path::Path,
-    sync::atomic::Ordering::Relaxed,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering::Relaxed},
+    },
 };
 
-use nix::{unistd, unistd::User};
+use nix::{
+    errno::Errno,
+    poll,
+    poll::{PollFd, PollFlags, PollTimeout},
+    unistd,
+    unistd::User,
+};
 use termion::{
     cursor::DetectCursorPos,
     event,
@@ -22,7 +32,7 @@ use termion::{
 };
 use unicode_width::UnicodeWidthChar;
 
-use crate::{ShellCore, error::input::InputError, file_check, utils::file};
+use crate::{ShellCore, error::input::InputError, exit, file_check, utils::file};
 
 struct Terminal {
     prompt:               String,
@@ -91,6 +101,10 @@ impl Terminal {
         let ansi_on_prompt = oct_to_hex_in_str(&raw_prompt);
 
         let replaced_prompt = Self::make_prompt_string(&ansi_on_prompt);
+        Self::new_with_prompt(&replaced_prompt)
+    }
+
+    pub fn new_with_prompt(replaced_prompt: &str) -> Self {
         let prompt = replaced_prompt.replace("\\[", "").replace("\\]", "").to_string();
         print!("{}", prompt);
         io::stdout().flush().unwrap();
@@ -106,7 +120,7 @@ impl Terminal {
             head:                 prompt.chars().count(),
             hist_ptr:             0,
             size:                 Terminal::size(),
-            prompt_width_map:     Self::make_width_map(&replaced_prompt),
+            prompt_width_map:     Self::make_width_map(replaced_prompt),
             prev_key:             event::Key::Char('a'),
             tab_num:              0,
             completion_candidate: String::new(),
@@ -354,6 +368,24 @@ impl Terminal {
         self.replace_input(&s);
     }
 
+    fn report_jobs(&mut self, core: &mut ShellCore) {
+        if core.jobtable_check_status().is_err() || !core.jobtable_has_status_change() {
+            return;
+        }
+
+        self.goto(self.chars.len());
+        self.write("\r\n");
+        self.flush();
+
+        let _ = self.stdout.suspend_raw_mode();
+        core.jobtable_print_status_change();
+        let _ = self.stdout.activate_raw_mode();
+
+        self.prompt_row = self.stdout.cursor_pos().unwrap_or((1, 1)).1 as usize;
+        self.check_scroll();
+        self.rewrite(true);
+    }
+
     pub fn cloop(&mut self) {
         print!("\x07");
         self.flush();
@@ -370,6 +402,34 @@ impl Terminal {
     }
 }
 
+/* reads fd 0 without buffering and gives up waiting on SIGHUP
+ * or when a job changes its state under set -b or while the rest
+ * of a suspended compound command waits for its job */
+struct KeyInput {
+    sigchld: Arc<AtomicBool>,
+    sighup:  Arc<AtomicBool>,
+    notify:  bool,
+}
+
+impl Read for KeyInput {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.sighup.load(Relaxed) || self.notify && self.sigchld.swap(false, Relaxed) {
+                return Err(ErrorKind::WouldBlock.into());
+            }
+
+            let stdin = io::stdin();
+            let mut fds = [PollFd::new(stdin.as_fd(), PollFlags::POLLIN)];
+            match poll::poll(&mut fds, PollTimeout::from(100u8)) {
+                Ok(0) | Err(Errno::EINTR) => continue,
+                _ => break,
+            }
+        }
+
+        unistd::read(0, buf).map_err(io::Error::from)
+    }
+}
+
 fn signal_check(core: &mut ShellCore, term: &mut Terminal) -> Result<bool, InputError> {
     if core.sigint.load(Relaxed) || core.trapped.iter_mut().any(|t| t.0.load(Relaxed)) {
         term.write("\r\n");
@@ -380,20 +440,57 @@ fn signal_check(core: &mut ShellCore, term: &mut Terminal) -> Result<bool, Input
 
 pub fn read_line(core: &mut ShellCore, prompt: &str) -> Result<String, InputError> {
     let mut term = Terminal::new(core, prompt);
-    signal_check(core, &mut term)?;
+    edit_line(core, &mut term)
+}
+
+// for read -e
+pub fn read_line_with_text(core: &mut ShellCore, prompt: &str, text: &str) -> Result<String, InputError> {
+    let mut term = Terminal::new_with_prompt(prompt);
+    text.chars().for_each(|c| term.insert(c));
+
+    let ans = edit_line(core, &mut term);
+    if ans.is_ok() {
+        core.history.remove(0);
+    }
+    ans
+}
+
+fn edit_line(core: &mut ShellCore, term: &mut Terminal) -> Result<String, InputError> {
+    signal_check(core, term)?;
 
     core.history.insert(0, String::new());
 
-    for c in io::stdin().keys() {
-        let c = c.unwrap();
+    let input = KeyInput {
+        sigchld: Arc::clone(&core.sigchld),
+        sighup:  Arc::clone(&core.sighup),
+        notify:  core.options.query("notify") || core.job_table.iter().any(|j| j.waits_for_resume()),
+    };
+    for c in input.keys() {
+        let c = match c {
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                if core.sighup.load(Relaxed) {
+                    let _ = term.stdout.suspend_raw_mode();
+                    exit::check_sighup(core);
+                    let _ = term.stdout.activate_raw_mode();
+                }
+                match core.options.query("notify") {
+                    true => term.report_jobs(core),
+                    false => {
+                        let _ = core.jobtable_check_status();
+                    },
+                }
+                continue;
+            },
+            c => c.unwrap(),
+        };
 
-        if let Err(e) = signal_check(core, &mut term) {
+        if let Err(e) = signal_check(core, term) {
             core.history.remove(0);
             return Err(e);
         }
 
         term.check_terminal_size();
-        match key::action(core, &mut term, &c) {
+        match key::action(core, term, &c) {
             Ok(true) => break,
             Ok(false) => term.prev_key = c,
             Err(e) => {
@@ -115,6 +115,10 @@ fn set_short_options(args: &mut Vec<String>, core: &mut ShellCore) {
         core.db.flags += "b";
     }
 
+    if arg::consume_option("-l", args) {
+        let _ = core.shopts.set("login_shell", true);
+    }
+
     if let Err(e) = option::set_options(core, &mut args[1..].to_vec()) {
         e.print(core);
         core.db.exit_status = 2;
@@ -154,6 +158,9 @@ fn main() {
     }
 
     let mut core = ShellCore::new();
+    if command.starts_with('-') {
+        let _ = core.shopts.set("login_shell", true);
+    }
     set_o_options(&mut args, &mut core);
     set_short_options(&mut args, &mut core);
 
@@ -196,9 +203,12 @@ fn main_loop(core: &mut ShellCore, command: &String) {
 
     if core.script_name != "-" {
         core.db.flags.retain(|f| f != 'i');
-        if let Err(_) = feeder.set_file(&core.script_name) {
-            eprintln!("{}: {}: No such file or directory", command, &core.script_name);
-            process::exit(2);
+        match feeder.set_file(&core.script_name) {
+            Ok(fd) => core.fds.set_internal(fd, "script"),
+            Err(_) => {
+                eprintln!("{}: {}: No such file or directory", command, &core.script_name);
+                process::exit(2);
+            },
         }
     }
 
@@ -209,7 +219,10 @@ fn main_loop(core: &mut ShellCore, command: &String) {
     loop {
         match feed_script(&mut feeder, core) {
             (true, false) => {},
-            (false, true) => break,
+            (false, true) => match exit::jobs_remain(core) {
+                true => {},
+                false => break,
+            },
             _ => parse_and_exec(&mut feeder, core, true),
         }
 
@@ -222,6 +235,7 @@ fn main_loop(core: &mut ShellCore, command: &String) {
 }
 
 fn feed_script(feeder: &mut Feeder, core: &mut ShellCore) -> (bool, bool) {
+    exit::check_sighup(core);
     if let Err(e) = core.jobtable_check_status() {
         //(continue, break)
         e.print(core);
@@ -246,9 +260,13 @@ fn parse_and_exec(feeder: &mut Feeder, core: &mut ShellCore, set_hist: bool) {
     core.sigint.store(false, Relaxed);
     match Script::parse(feeder, core, false) {
         Ok(Some(mut s)) => {
+            let exit_warned = core.exit_warned;
             if let Err(e) = s.exec(core) {
                 e.print(core);
             }
+            if exit_warned {
+                core.exit_warned = false;
+            }
             if set_hist {
                 set_history(core, &s.get_text());
             }
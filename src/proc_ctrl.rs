@@ -1,28 +1,40 @@
 // SPDX-FileCopyrightText: 2024 Ryuichi Ueda ryuichiueda@gmail.com
 // SPDX-License-Identifier: BSD-3-Clause
 
-use std::{ffi::CString, process, sync::atomic::Ordering::Relaxed};
+use std::{env, ffi::CString, process, sync::atomic::Ordering::Relaxed};
 
 use nix::{
     errno::Errno,
     sys::{
-        resource,
-        resource::UsageWho,
         signal::Signal,
+        termios,
+        termios::SetArg,
+        time::TimeVal,
         wait,
         wait::{WaitPidFlag, WaitStatus},
     },
-    time::{ClockId, clock_gettime},
     unistd,
     unistd::Pid,
 };
 
-use crate::{Feeder, Script, ShellCore, error, error::exec::ExecError, exit, signal, utils::c_string};
+use crate::{
+    Feeder, Script, ShellCore,
+    core::MeasuredTime,
+    error,
+    error::exec::ExecError,
+    exit, signal,
+    utils::{c_string, file},
+};
 
-pub fn wait_pipeline(core: &mut ShellCore, pids: Vec<Option<Pid>>, exclamation: bool, time: bool) -> Vec<WaitStatus> {
+pub fn wait_pipeline(
+    core: &mut ShellCore,
+    pids: Vec<Option<Pid>>,
+    exclamation: bool,
+    time: Option<MeasuredTime>,
+) -> Vec<WaitStatus> {
     if pids.len() == 1 && pids[0] == None {
-        if time {
-            show_time(core);
+        if let Some(t) = time {
+            show_time(core, &t);
         }
         if exclamation {
             core.flip_exit_status();
@@ -46,10 +58,11 @@ pub fn wait_pipeline(core: &mut ShellCore, pids: Vec<Option<Pid>>, exclamation:
         }
     }
 
-    if time {
-        show_time(core);
+    if let Some(t) = time {
+        show_time(core, &t);
     }
-    set_foreground(core);
+    let abnormal = ans.iter().any(|ws| matches!(ws, WaitStatus::Stopped(..) | WaitStatus::Signaled(..)));
+    restore_terminal(core, abnormal);
     let _ = core.db.set_array("PIPESTATUS", Some(pipestatus.iter().map(|e| e.to_string()).collect()), None);
 
     if core.options.query("pipefail") {
@@ -80,10 +93,7 @@ fn wait_process(core: &mut ShellCore, child: Pid) -> WaitStatus {
     core.db.exit_status = match ws {
         Ok(WaitStatus::Exited(_pid, status)) => status,
         Ok(WaitStatus::Signaled(pid, signal, coredump)) => error::signaled(pid, signal, coredump),
-        Ok(WaitStatus::Stopped(pid, signal)) => {
-            eprintln!("Stopped Pid: {:?}, Signal: {:?}", pid, signal);
-            148
-        },
+        Ok(WaitStatus::Stopped(..)) => 148,
         Ok(unsupported) => {
             ExecError::UnsupportedWaitStatus(unsupported).print(core);
             1
@@ -113,10 +123,34 @@ fn set_foreground(core: &ShellCore) {
     }
 
     signal::ignore(Signal::SIGTTOU); //SIGTTOUを無視
-    unistd::tcsetpgrp(fd, pgid).expect(&error::internal("cannot get the terminal"));
+    let _ = unistd::tcsetpgrp(fd, pgid);
     signal::restore(Signal::SIGTTOU); //SIGTTOUを受け付け
 }
 
+/* takes the terminal back after a foreground job. The terminal modes are
+ * restored if the job was stopped or killed, and kept for later otherwise. */
+pub fn restore_terminal(core: &mut ShellCore, abnormal: bool) {
+    if core.is_subshell {
+        return;
+    }
+    set_foreground(core);
+
+    let fd = match core.tty_fd.as_ref() {
+        Some(fd) => fd,
+        _ => return,
+    };
+
+    if !abnormal {
+        if let Ok(t) = termios::tcgetattr(fd) {
+            core.tty_termios = Some(t);
+        }
+    } else if let Some(t) = core.tty_termios.as_ref() {
+        signal::ignore(Signal::SIGTTOU);
+        let _ = termios::tcsetattr(fd, SetArg::TCSADRAIN, t);
+        signal::restore(Signal::SIGTTOU);
+    }
+}
+
 pub fn set_pgid(core: &ShellCore, pid: Pid, pgid: Pid) {
     let _ = unistd::setpgid(pid, pgid);
     let lastpipe = !core.db.flags.contains('m') && core.shopts.query("lastpipe");
@@ -127,41 +161,132 @@ pub fn set_pgid(core: &ShellCore, pid: Pid, pgid: Pid) {
     }
 }
 
-fn show_time(core: &ShellCore) {
-    let real_end_time = clock_gettime(ClockId::CLOCK_MONOTONIC).unwrap();
+const TIMEFORMAT_DEFAULT: &str = "\nreal\t%3lR\nuser\t%3lU\nsys\t%3lS";
+const TIMEFORMAT_POSIX: &str = "real %2R\nuser %2U\nsys %2S";
 
-    let core_usage = resource::getrusage(UsageWho::RUSAGE_SELF).unwrap();
-    let children_usage = resource::getrusage(UsageWho::RUSAGE_CHILDREN).unwrap();
+fn format_seconds(usec: i64, precision: usize, long: bool) -> String {
+    let sec = usec / 1_000_000;
+    let frac = format!("{:03}", usec % 1_000_000 / 1000);
+    let frac = match precision {
+        0 => String::new(),
+        p => format!(".{}", &frac[..p]),
+    };
 
-    let real_diff = real_end_time - core.measured_time.real;
-    eprintln!("\nreal\t{}m{}.{:06}s", real_diff.tv_sec() / 60, real_diff.tv_sec() % 60, real_diff.tv_nsec() / 1000);
-    let user_diff = core_usage.user_time() + children_usage.user_time() - core.measured_time.user;
-    eprintln!("user\t{}m{}.{:06}s", user_diff.tv_sec() / 60, user_diff.tv_sec() % 60, user_diff.tv_usec());
-    let sys_diff = core_usage.system_time() + children_usage.system_time() - core.measured_time.sys;
-    eprintln!("sys \t{}m{}.{:06}s", sys_diff.tv_sec() / 60, sys_diff.tv_sec() % 60, sys_diff.tv_usec());
+    match long {
+        true => format!("{}m{}{}s", sec / 60, sec % 60, frac),
+        false => format!("{}{}", sec, frac),
+    }
 }
 
-pub fn exec_command(args: &Vec<String>, core: &mut ShellCore, fullpath: &String) -> ! {
-    let cargs = c_string::to_cargs(args);
-    let cfullpath = CString::new(fullpath.to_string()).unwrap();
+/* expands %[p][l]R, %[p][l]U, %[p][l]S, %P and %% in TIMEFORMAT */
+fn time_report(format: &str, real: i64, user: i64, sys: i64) -> Result<String, char> {
+    let mut ans = String::new();
+    let mut chars = format.chars().peekable();
 
-    if !fullpath.is_empty() {
-        let _ = unistd::execv(&cfullpath, &cargs);
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            ans.push(c);
+            continue;
+        }
+
+        match chars.peek() {
+            None => ans.push('%'),
+            Some('%') => ans.push('%'),
+            Some('P') => {
+                let cpu = match real {
+                    0 => 0,
+                    _ => std::cmp::min((user + sys) * 10000 / real, 10000),
+                };
+                ans += &format!("{}.{:02}", cpu / 100, cpu % 100);
+            },
+            Some(_) => {
+                let precision = match chars.next_if(|c| c.is_ascii_digit()) {
+                    Some(d) => std::cmp::min(d as usize - '0' as usize, 3),
+                    None => 3,
+                };
+                let long = chars.next_if_eq(&'l').is_some();
+                let usec = match chars.peek() {
+                    Some('R') => real,
+                    Some('U') => user,
+                    Some('S') => sys,
+                    c => return Err(*c.unwrap_or(&'\0')),
+                };
+                ans += &format_seconds(usec, precision, long);
+            },
+        }
+        chars.next();
     }
-    let result = unistd::execvp(&cargs[0], &cargs);
 
-    match result {
-        Err(Errno::E2BIG) => exit::arg_list_too_long(&args[0], core),
-        Err(Errno::EACCES) => exit::permission_denied(&args[0], core),
-        Err(Errno::ENOENT) => run_command_not_found(&args[0], core),
-        Err(err) => {
+    Ok(ans)
+}
+
+fn show_time(core: &mut ShellCore, start: &MeasuredTime) {
+    let end = MeasuredTime::start(start.posix);
+
+    // TIMEFORMAT may still sit only in the environment until it is read once
+    let exist = core.db.exist("TIMEFORMAT") || env::var_os("TIMEFORMAT").is_some();
+    let format = match (start.posix, exist) {
+        (true, _) => TIMEFORMAT_POSIX.to_string(),
+        (false, true) => core.db.get_param("TIMEFORMAT").unwrap_or_default(),
+        (false, false) => TIMEFORMAT_DEFAULT.to_string(),
+    };
+    if format.is_empty() {
+        return;
+    }
+
+    let real = end.real - start.real;
+    let real = real.tv_sec() * 1_000_000 + real.tv_nsec() / 1000;
+    let user = end.user - start.user;
+    let sys = end.sys - start.sys;
+    let usec = |t: TimeVal| t.tv_sec() * 1_000_000 + t.tv_usec();
+
+    match time_report(&format, real, usec(user), usec(sys)) {
+        Ok(report) => eprintln!("{}", report),
+        Err(c) => {
+            let msg = format!("TIMEFORMAT: `{}': invalid format character", c);
+            error::print(&msg, core);
+        },
+    }
+}
+
+pub fn exec_command(args: &[String], core: &mut ShellCore, fullpath: &str) -> ! {
+    match replace_process(args, &args[0], fullpath, false) {
+        Errno::E2BIG => exit::arg_list_too_long(&args[0], core),
+        Errno::EACCES => exit::permission_denied(&args[0], core),
+        Errno::ENOENT => run_command_not_found(&args[0], core),
+        err => {
             eprintln!("Failed to execute. {:?}", err);
             process::exit(127)
         },
-        _ => exit::internal("never come here"),
     }
 }
 
+/* replaces the process with args[0], giving argv0 as its argv[0].
+ * clear_env: starts the command with an empty environment.
+ * Returns only when the exec fails. */
+pub fn replace_process(args: &[String], argv0: &str, fullpath: &str, clear_env: bool) -> Errno {
+    let mut cargs = c_string::to_cargs(&args.to_vec());
+    cargs[0] = c_string::to_carg(&argv0.to_string());
+
+    if clear_env {
+        let path = match (fullpath.is_empty(), args[0].contains('/')) {
+            (false, _) => fullpath.to_string(),
+            (true, true) => args[0].clone(),
+            (true, false) => match file::search_command(&args[0]) {
+                Some(path) => path,
+                None => return Errno::ENOENT,
+            },
+        };
+        let env: Vec<CString> = vec![];
+        return unistd::execve(&c_string::to_carg(&path), &cargs, &env).unwrap_err();
+    }
+
+    if !fullpath.is_empty() {
+        let _ = unistd::execv(&c_string::to_carg(&fullpath.to_string()), &cargs);
+    }
+    unistd::execvp(&c_string::to_carg(&args[0]), &cargs).unwrap_err()
+}
+
 fn run_command_not_found(arg: &String, core: &mut ShellCore) -> ! {
     if core.db.functions.contains_key("command_not_found_handle") {
         let s = "command_not_found_handle ".to_owned() + &arg.clone();
@@ -6,13 +6,16 @@ use std::{
     thread, time,
 };
 
-use nix::sys::{
-    signal,
-    signal::{SigHandler, Signal},
+use nix::{
+    fcntl,
+    sys::{
+        signal,
+        signal::{SigHandler, Signal},
+    },
 };
 use signal_hook::{consts, iterator::Signals};
 
-use crate::{Script, core::ShellCore, feeder::Feeder};
+use crate::{Script, core::ShellCore, elements::io, feeder::Feeder};
 
 pub fn ignore(sig: Signal) {
     unsafe { signal::signal(sig, SigHandler::SigIgn) }.expect("sush(fatal): cannot ignore signal");
@@ -28,11 +31,19 @@ pub fn run_signal_check(core: &mut ShellCore) {
         nix::unistd::dup2(2, fd).expect("sush(fatal): init error");
     }
 
+    let fds = io::open_fds();
     core.sigint.store(true, Relaxed);
     let sigint = Arc::clone(&core.sigint);
+    let sigchld = Arc::clone(&core.sigchld);
+    let sighup = Arc::clone(&core.sighup);
+
+    let mut sigs = vec![consts::SIGINT, consts::SIGCHLD];
+    if core.db.flags.contains('i') {
+        sigs.push(consts::SIGHUP); // passed to the jobs in exit::check_sighup
+    }
 
     thread::spawn(move || {
-        let mut signals = Signals::new(vec![consts::SIGINT]).expect("sush(fatal): cannot prepare signal data");
+        let mut signals = Signals::new(sigs).expect("sush(fatal): cannot prepare signal data");
 
         for fd in 3..10 {
             // release FD 3~9
@@ -43,9 +54,14 @@ pub fn run_signal_check(core: &mut ShellCore) {
         loop {
             thread::sleep(time::Duration::from_millis(100)); //0.1秒周期に変更
             for signal in signals.pending() {
-                if signal == consts::SIGINT {
-                    sigint.store(true, Relaxed);
-                    eprintln!("^C");
+                match signal {
+                    consts::SIGINT => {
+                        sigint.store(true, Relaxed);
+                        eprintln!("^C");
+                    },
+                    consts::SIGCHLD => sigchld.store(true, Relaxed),
+                    consts::SIGHUP => sighup.store(true, Relaxed),
+                    _ => {},
                 }
             }
         }
@@ -54,6 +70,12 @@ pub fn run_signal_check(core: &mut ShellCore) {
     while core.sigint.load(Relaxed) {
         thread::sleep(time::Duration::from_millis(1));
     }
+
+    for fd in io::open_fds().into_iter().filter(|fd| !fds.contains(fd)) {
+        // signal-hook keeps these fd numbers, so they are only marked close-on-exec
+        let _ = fcntl::fcntl(fd, fcntl::F_SETFD(fcntl::FdFlag::FD_CLOEXEC));
+        core.fds.set_internal(fd, "signal");
+    }
 } //thanks: https://dev.to/talzvon/handling-unix-kill-signals-in-rust-55g6
 
 pub fn input_interrupt_check(feeder: &mut Feeder, core: &mut ShellCore) -> bool {
@@ -99,3 +121,67 @@ pub fn check_trap(core: &mut ShellCore) {
 
     core.db.exit_status = bkup;
 }
+
+// Real-time signals are not available on every platform (e.g. macOS)
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn rt_range() -> Option<(i32, i32)> {
+    Some((nix::libc::SIGRTMIN(), nix::libc::SIGRTMAX()))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn rt_range() -> Option<(i32, i32)> {
+    None
+}
+
+// Signal numbers and names in the order and notation of bash's kill -l
+pub fn numbers() -> Vec<i32> {
+    let mut ans: Vec<i32> = Signal::iterator().map(|s| s as i32).collect();
+    ans.sort();
+    if let Some((min, max)) = rt_range() {
+        ans.extend(min..=max);
+    }
+    ans
+}
+
+pub fn to_name(num: i32) -> Option<String> {
+    if let Ok(s) = Signal::try_from(num) {
+        return Some(s.as_str().to_string());
+    }
+
+    let (min, max) = rt_range()?;
+    match num {
+        n if n == min => Some("SIGRTMIN".to_string()),
+        n if n == max => Some("SIGRTMAX".to_string()),
+        n if n > min && n - min <= (max - min) / 2 => Some(format!("SIGRTMIN+{}", n - min)),
+        n if n > min && n < max => Some(format!("SIGRTMAX-{}", max - n)),
+        _ => None,
+    }
+}
+
+// accepts a number or a name with or without SIG, case-insensitively
+pub fn to_num(spec: &str) -> Option<i32> {
+    if let Ok(n) = spec.parse::<i32>() {
+        return match n == 0 || to_name(n).is_some() {
+            true => Some(n),
+            false => None,
+        };
+    }
+
+    let upper = spec.to_uppercase();
+    let name = upper.strip_prefix("SIG").unwrap_or(&upper);
+    let (min, max) = rt_range().unwrap_or((1, 0));
+    let rt = match name {
+        "RTMIN" => Some(min),
+        "RTMAX" => Some(max),
+        _ => match (name.strip_prefix("RTMIN+"), name.strip_prefix("RTMAX-")) {
+            (Some(n), _) => n.parse::<i32>().ok().map(|n| min + n),
+            (_, Some(n)) => n.parse::<i32>().ok().map(|n| max - n),
+            _ => None,
+        },
+    };
+    if let Some(n) = rt {
+        return if (min..=max).contains(&n) { Some(n) } else { None };
+    }
+
+    Signal::iterator().find(|s| s.as_str()[3..] == *name).map(|s| s as i32)
+}
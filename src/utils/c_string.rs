@@ -1,7 +1,10 @@
 // SPDX-FileCopyrightText: 2025 Ryuichi Ueda <ryuichiueda@gmail.com>
 // SPDX-License-Identifier: BSD-3-Clause
 
-use std::ffi::CString;
+use std::{
+    ffi::{CString, OsString},
+    os::unix::ffi::OsStringExt,
+};
 
 pub fn to_carg(arg: &String) -> CString {
     let mut tmp = String::new();
@@ -31,3 +34,87 @@ pub fn to_carg(arg: &String) -> CString {
 pub fn to_cargs(args: &Vec<String>) -> Vec<CString> {
     args.iter().map(|a| to_carg(a)).collect()
 }
+
+pub fn to_os_string(arg: &str) -> OsString {
+    OsString::from_vec(to_carg(&arg.to_string()).into_bytes())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EscapeMode {
+    Format, // printf format
+    Arg,    // printf %b
+    Echo,   // echo -e
+}
+
+fn eat_digits(s: &[u8], pos: &mut usize, radix: u32, max: usize) -> Option<u32> {
+    let mut ans = None;
+    for _ in 0..max {
+        match s.get(*pos).and_then(|c| (*c as char).to_digit(radix)) {
+            Some(d) => ans = Some(ans.unwrap_or(0) * radix + d),
+            None => break,
+        }
+        *pos += 1;
+    }
+    ans
+}
+
+// The bool is true when \c stopped the output.
+pub fn unescape(s: &[u8], mode: EscapeMode) -> (Vec<u8>, bool) {
+    let mut ans = vec![];
+    let mut pos = 0;
+
+    while pos < s.len() {
+        let c = s[pos];
+        pos += 1;
+        if c != b'\\' {
+            ans.push(c);
+            continue;
+        }
+
+        let c = match s.get(pos) {
+            Some(c) => *c,
+            None => {
+                ans.push(b'\\');
+                break;
+            },
+        };
+        pos += 1;
+
+        match c {
+            b'a' => ans.push(7),
+            b'b' => ans.push(8),
+            b'e' | b'E' => ans.push(27),
+            b'f' => ans.push(12),
+            b'n' => ans.push(b'\n'),
+            b'r' => ans.push(b'\r'),
+            b't' => ans.push(b'\t'),
+            b'v' => ans.push(11),
+            b'\\' => ans.push(b'\\'),
+            b'"' | b'\'' | b'?' if mode == EscapeMode::Format => ans.push(c),
+            b'c' if mode != EscapeMode::Format => return (ans, true),
+            b'0' if mode != EscapeMode::Format => ans.push(eat_digits(s, &mut pos, 8, 3).unwrap_or(0) as u8),
+            b'0'..=b'7' if mode != EscapeMode::Echo => {
+                pos -= 1;
+                ans.push(eat_digits(s, &mut pos, 8, 3).unwrap_or(0) as u8);
+            },
+            b'x' | b'u' | b'U' => {
+                let max = match c {
+                    b'x' => 2,
+                    b'u' => 4,
+                    _ => 8,
+                };
+                match (c, eat_digits(s, &mut pos, 16, max)) {
+                    (b'x', Some(n)) => ans.push(n as u8),
+                    (_, Some(n)) => {
+                        let ch = char::from_u32(n).unwrap_or(char::REPLACEMENT_CHARACTER);
+                        ans.extend(ch.to_string().bytes());
+                    },
+                    (_, None) => ans.extend([b'\\', c]),
+                }
+            },
+            _ => ans.extend([b'\\', c]),
+        }
+    }
+
+    (ans, false)
+}
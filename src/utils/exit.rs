@@ -1,17 +1,71 @@
 // SPDX-FileCopyrightText: 2024 Ryuichi Ueda ryuichiueda@gmail.com
 // SPDX-License-Identifier: BSD-3-Clause
 
-use std::process;
+use std::{process, sync::atomic::Ordering::Relaxed};
+
+use nix::{
+    sys::{signal, signal::Signal},
+    unistd,
+};
 
 use crate::{Feeder, Script, ShellCore, error};
 
 pub fn normal(core: &mut ShellCore) -> ! {
     run_script(core);
 
+    if core.db.flags.contains('i') && core.shopts.query("login_shell") && core.shopts.query("huponexit") {
+        core.jobtable_hangup();
+    }
+
     core.write_history_to_file();
     process::exit(core.db.exit_status % 256)
 }
 
+/* an interactive shell hangs up its jobs and dies of SIGHUP unless it is trapped */
+pub fn check_sighup(core: &mut ShellCore) {
+    if !core.sighup.swap(false, Relaxed) || core.traplist.iter().any(|t| t.0 == Signal::SIGHUP as i32) {
+        return;
+    }
+
+    core.jobtable_hangup();
+    run_script(core);
+    core.write_history_to_file();
+
+    crate::signal::restore(Signal::SIGHUP);
+    let _ = signal::kill(unistd::getpid(), Signal::SIGHUP);
+    process::exit(129)
+}
+
+/* warns once instead of exiting when jobs remain (stopped ones, or running ones under checkjobs) */
+pub fn jobs_remain(core: &mut ShellCore) -> bool {
+    if !core.db.flags.contains('i') || !core.options.query("monitor") || core.is_subshell || core.exit_warned {
+        return false;
+    }
+
+    let _ = core.jobtable_check_status();
+    let checkjobs = core.shopts.query("checkjobs");
+    let stopped = core.job_table.iter().any(|j| j.display_status == "Stopped");
+    let running = checkjobs && core.job_table.iter().any(|j| j.display_status == "Running");
+
+    if !stopped && !running {
+        return false;
+    }
+
+    match stopped {
+        true => eprintln!("There are stopped jobs."),
+        false => eprintln!("There are running jobs."),
+    }
+    if checkjobs {
+        let priority = &core.job_table_priority;
+        core.job_table.iter().for_each(|j| {
+            j.print(priority, false);
+        });
+    }
+
+    core.exit_warned = true;
+    true
+}
+
 fn run_script(core: &mut ShellCore) {
     if core.exit_script_run {
         return;
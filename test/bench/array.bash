@@ -0,0 +1,79 @@
+#!/bin/bash
+# Benchmarks of indexed arrays
+#   usage: sush test/bench/array.bash [number of elements]
+# The same script runs on Bash for comparison.
+
+N=${1:-100000}
+
+bench () {
+    local name=$1
+    shift
+    local start=${EPOCHREALTIME/./}
+    "$@"
+    local end=${EPOCHREALTIME/./}
+    printf '%-24s %8d ms\n' "$name" $(( (end - start) / 1000 ))
+}
+
+append () {
+    a=()
+    for ((i=0; i<N; i++)); do
+        a+=("$i")
+    done
+}
+
+append_int () {
+    declare -ga ia
+    declare -gi ia
+    for ((i=0; i<N; i++)); do
+        ia+=($i)
+    done
+}
+
+sparse () {
+    s=()
+    s[100000000]=end
+    for ((i=0; i<N; i++)); do
+        s[i*1000]=$i
+    done
+}
+
+read_elems () {
+    for ((i=0; i<N; i++)); do
+        x=${a[i]}
+    done
+}
+
+negative_index () {
+    for ((i=1; i<=N; i++)); do
+        x=${a[-i]}
+    done
+}
+
+slices () {
+    for ((i=0; i<N/10; i++)); do
+        x=("${s[@]:i*100:5}")
+    done
+}
+
+indexes () {
+    for ((i=0; i<10; i++)); do
+        x=("${!s[@]}")
+    done
+}
+
+all_values () {
+    for ((i=0; i<10; i++)); do
+        x=("${s[@]}")
+    done
+}
+
+bench "append" append
+bench "append (declare -i)" append_int
+bench "sparse set" sparse
+bench "read" read_elems
+bench "negative index" negative_index
+bench "slice of sparse array" slices
+bench "indexes of sparse array" indexes
+bench "values of sparse array" all_values
+
+[ "${#a[@]}" -eq "$N" ] && [ "${a[-1]}" -eq "$((N-1))" ] && [ "${#s[@]}" -eq "$((N+1))" ] || echo NG